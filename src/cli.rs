@@ -17,11 +17,36 @@ pub enum Commands {
     #[command(alias = "c")]
     Connect(ConnectArgs),
 
+    /// Start a new config repo with a starter ralf.conf and connect to it
+    New(NewArgs),
+
     #[command(aliases = ["d", "pull"])]
-    Download,
+    Download(DownloadArgs),
 
     #[command(aliases = ["u", "push"])]
-    Upload,
+    Upload(UploadArgs),
+
+    /// Show uncommitted changes, ahead/behind counts and whether aliases are current
+    #[command(alias = "st")]
+    Status(StatusArgs),
+
+    /// Show the branches of the config repo, or switch the one ralf follows
+    Branch(BranchArgs),
+
+    /// List kept generations of the aliases files with what each one changed
+    History {
+        #[arg(long, help = "Print machine-readable JSON")]
+        json: bool,
+    },
+
+    /// Show or change ralf's own settings (config.toml)
+    Config(ConfigArgs),
+
+    /// Restore a kept generation of the aliases files (default: the one before)
+    Rollback {
+        /// Generation number from 'ralf history'
+        n: Option<usize>,
+    },
 
     #[command(alias = "g")]
     Generate,
@@ -29,22 +54,54 @@ pub enum Commands {
     #[command(alias = "s")]
     Save,
 
+    /// Edit a config layer, or the file that defines an alias at its line
     #[command(alias = "e")]
     Edit {
-        what: Option<String>,
+        /// local | machine [<id>] | <alias> [<sub>]
+        #[arg(num_args = 0..=2, value_names = ["WHAT", "NAME"])]
+        what: Vec<String>,
     },
 
     #[command(alias = "w")]
     Which {
         code: String,
         subcode: Option<String>,
+        #[arg(long, help = "Show which layer defined or overrode the alias")]
+        trace: bool,
     },
 
-    #[command(alias = "m")]
-    Machine {
-        name: Option<String>,
+    /// Run an alias as the generated shell function would, without sourcing anything
+    #[command(alias = "r")]
+    Run {
+        /// Alias, then its subcommand and arguments, passed on unchanged
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_names = ["ALIAS", "ARGS"]
+        )]
+        words: Vec<String>,
+    },
+
+    /// Show the exact command line an alias invocation runs in each shell
+    #[command(alias = "x")]
+    Expand {
+        #[arg(long, help = "Only show this shell (bash, zsh or fish)")]
+        shell: Option<String>,
+        /// Alias, then its subcommand and arguments (use -- before them); a
+        /// trailing `--shell <sh>` is taken as the option
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_names = ["ALIAS", "ARGS"]
+        )]
+        words: Vec<String>,
     },
 
+    #[command(alias = "m")]
+    Machine(MachineArgs),
+
     #[command(alias = "h")]
     Help {
         topic: Option<String>,
@@ -60,8 +117,35 @@ pub enum Commands {
     /// Remove generated aliases and local ralf markers (does not delete your repo)
     Reset,
 
-    Upgrade,
+    /// Replace this binary with the latest release from the release manifest
+    Upgrade(UpgradeArgs),
     Info,
+
+    /// Report alias usage recorded by the generated functions (opt-in)
+    Stats(StatsArgs),
+
+    /// Check the merged config for aliases that shadow keywords, builtins or commands
+    Check,
+
+    /// List the effective aliases after merging all layers
+    #[command(alias = "ls")]
+    List(ListArgs),
+
+    /// Fuzzy-search alias names, commands and descriptions
+    Search(SearchArgs),
+
+    /// Compare merged aliases between machines, revisions or the generated file
+    Diff(DiffArgs),
+
+    /// Add an alias or subcommand: ralf add <alias> [sub] <command>
+    Add(AddArgs),
+
+    /// Change (or create) an alias or subcommand: ralf set <alias> [sub] <command>
+    Set(SetArgs),
+
+    /// Remove an alias with its subcommands, or a single subcommand
+    #[command(alias = "remove")]
+    Rm(RmArgs),
 }
 
 #[derive(Args, Debug)]
@@ -75,4 +159,252 @@ pub struct ConnectArgs {
     pub yes: bool,
     #[arg(long)]
     pub tui: bool,
+    #[arg(
+        short,
+        long,
+        help = "Check out this branch instead of the default (remembered for reconnects)"
+    )]
+    pub branch: Option<String>,
+    #[arg(
+        long,
+        value_parser = ["git", "dir", "copy"],
+        help = "How to sync: git (default), dir (use a synced directory as is) or copy (mirror a local or mounted path)"
+    )]
+    pub sync: Option<String>,
+    #[arg(
+        long,
+        help = "Clone (or copy) into this directory instead of $XDG_DATA_HOME/ralf/repo"
+    )]
+    pub dir: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct UpgradeArgs {
+    #[arg(long, help = "Only report whether a newer release is available")]
+    pub check: bool,
+    #[arg(
+        long,
+        help = "Release manifest URL or file (default: the upgrade_url setting)"
+    )]
+    pub manifest: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct NewArgs {
+    /// Directory for the repo (default: $XDG_DATA_HOME/ralf/repo)
+    pub dir: Option<String>,
+    #[arg(long, help = "Add this URL as the origin remote")]
+    pub remote: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct DownloadArgs {
+    #[arg(
+        long,
+        conflicts_with = "theirs",
+        help = "Resolve alias conflicts with this machine's version"
+    )]
+    pub ours: bool,
+    #[arg(long, help = "Resolve alias conflicts with the repository's version")]
+    pub theirs: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: Option<ConfigAction>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// List every setting with its value and where it comes from
+    #[command(alias = "ls")]
+    List {
+        #[arg(long, help = "Print machine-readable JSON")]
+        json: bool,
+    },
+    /// Print the effective value of a setting
+    Get { key: String },
+    /// Store a setting in config.toml
+    Set { key: String, value: String },
+    /// Remove a setting from config.toml
+    Unset { key: String },
+}
+
+#[derive(Args, Debug)]
+pub struct BranchArgs {
+    /// Branch to switch to and follow from now on
+    pub name: Option<String>,
+    #[arg(
+        short,
+        long,
+        requires = "name",
+        help = "Create the branch from the current one"
+    )]
+    pub create: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    #[arg(long, help = "Fetch from the remote first so ahead/behind is current")]
+    pub fetch: bool,
+    #[arg(long, help = "Print machine-readable JSON")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct UploadArgs {
+    #[arg(
+        short,
+        long,
+        help = "Commit message (default: a summary of the alias changes)"
+    )]
+    pub message: Option<String>,
+    #[arg(
+        long,
+        help = "Stage every change in the repo, not just the files ralf manages"
+    )]
+    pub all: bool,
+    #[arg(short = 'y', long = "yes", help = "Don't ask before committing")]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct MachineArgs {
+    #[command(subcommand)]
+    pub action: Option<MachineAction>,
+    /// Set the current machine id
+    pub name: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "name",
+        help = "Show how the machine id was chosen and which rule matched"
+    )]
+    pub explain: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MachineAction {
+    /// List machine overlays with alias counts and their last commit
+    #[command(alias = "ls")]
+    List,
+    /// Create an overlay for a machine, optionally copying another one
+    New {
+        id: String,
+        #[arg(long, help = "Copy the overlay of this machine id")]
+        from: Option<String>,
+    },
+    /// Rename a machine overlay
+    Rename { from: String, to: String },
+    /// Delete a machine overlay
+    #[command(alias = "remove")]
+    Rm { id: String },
+    /// Show the effective aliases for a machine id (defaults to the current one)
+    Show { id: Option<String> },
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[arg(long, help = "Start recording alias usage (regenerates aliases)")]
+    pub enable: bool,
+    #[arg(long, conflicts_with = "enable", help = "Stop recording alias usage")]
+    pub disable: bool,
+    #[arg(long, help = "Machine id to report on (defaults to the current one)")]
+    pub machine: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "Number of most-used aliases to show"
+    )]
+    pub top: usize,
+    #[arg(
+        long,
+        default_value_t = 90,
+        help = "Days without use before an alias is stale"
+    )]
+    pub stale_days: u64,
+    #[arg(long, help = "List aliases that look safe to remove")]
+    pub suggest: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    #[arg(
+        long,
+        help = "Only aliases whose effective definition is in this layer (base, group, machine, local)"
+    )]
+    pub layer: Option<String>,
+    #[arg(long, help = "Only aliases whose name starts with this prefix")]
+    pub prefix: Option<String>,
+    #[arg(long, help = "Print JSON instead of a table")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    pub query: String,
+    #[arg(long, help = "Print JSON instead of a table")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    #[arg(
+        long,
+        num_args = 1,
+        help = "Machine id to compare; once against this machine, twice against each other"
+    )]
+    pub machine: Vec<String>,
+    #[arg(
+        long,
+        help = "Compare the working tree with this revision (default: HEAD); the local overlay is left out"
+    )]
+    pub rev: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["machine", "rev"],
+        help = "Compare the aliases file on disk with what 'ralf save' would write"
+    )]
+    pub generated: bool,
+    #[arg(long, help = "Print JSON instead of a diff")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct LayerArgs {
+    #[arg(
+        long,
+        conflicts_with = "local",
+        help = "Edit the overlay of this machine id"
+    )]
+    pub machine: Option<String>,
+    #[arg(long, help = "Edit the local overlay (ralf.local.conf)")]
+    pub local: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AddArgs {
+    #[arg(num_args = 2..=3, value_names = ["ALIAS", "SUB", "COMMAND"])]
+    pub words: Vec<String>,
+    #[arg(long, help = "Replace an existing definition")]
+    pub force: bool,
+    #[command(flatten)]
+    pub layer: LayerArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct SetArgs {
+    #[arg(num_args = 2..=3, value_names = ["ALIAS", "SUB", "COMMAND"])]
+    pub words: Vec<String>,
+    #[command(flatten)]
+    pub layer: LayerArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct RmArgs {
+    pub alias: String,
+    pub sub: Option<String>,
+    #[command(flatten)]
+    pub layer: LayerArgs,
 }
@@ -0,0 +1,83 @@
+use crate::cli::{AddArgs, LayerArgs};
+use crate::config_merge;
+use crate::domain::edit;
+use crate::paths;
+use anyhow::{bail, Result};
+use std::fs;
+
+pub fn run(args: AddArgs) -> Result<()> {
+    write_alias(&args.words, &args.layer, args.force)
+}
+
+/// Shared by `add` and `set`: write `alias [sub] command` into the chosen layer.
+/// Without `replace`, an existing definition in that layer is an error.
+pub fn write_alias(words: &[String], layer: &LayerArgs, replace: bool) -> Result<()> {
+    let (alias, sub, cmd) = match words {
+        [a, c] => (a, None, c),
+        [a, s, c] => (a, Some(s), c),
+        _ => bail!("expected <alias> [sub] <command>"),
+    };
+    for name in std::iter::once(alias).chain(sub) {
+        if !edit::valid_name(name) {
+            bail!(
+                "invalid name '{}' (use letters, digits and dashes only)",
+                name
+            );
+        }
+    }
+    let cmd = cmd.trim();
+    if cmd.is_empty() || cmd.contains('\n') {
+        bail!("the command must be a single non-empty line");
+    }
+
+    let p = paths::find_config_or_exit()?;
+    let target = config_merge::layer_target(&p, layer.machine.as_deref(), layer.local)?;
+    let text = fs::read_to_string(&target).unwrap_or_default();
+    let label = match sub {
+        Some(s) => format!("{} {}", alias, s),
+        None => alias.clone(),
+    };
+
+    let exists = match sub {
+        Some(s) => edit::has_sub(&text, alias, s),
+        None => edit::has_alias(&text, alias),
+    };
+    if exists && !replace {
+        bail!(
+            "'{}' already exists in {} (use --force to replace it)",
+            label,
+            paths::friendly(&target)
+        );
+    }
+
+    let new_text = match sub {
+        None => edit::set_parent(&text, alias, cmd),
+        Some(s) => {
+            let mut base = text.clone();
+            if !edit::has_alias(&base, alias) {
+                // Overlays replace the parent, so carry the current one over unchanged
+                let Some(parent) = config_merge::parent_below(&p, &target, alias)? else {
+                    bail!(
+                        "no alias '{}'; add it first with 'ralf add {} <command>'",
+                        alias,
+                        alias
+                    );
+                };
+                base = edit::set_parent(&base, alias, &parent);
+            }
+            edit::set_sub(&base, alias, s, cmd)?
+        }
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, new_text)?;
+    println!(
+        "{} '{}' in {}",
+        if exists { "Updated" } else { "Added" },
+        label,
+        paths::friendly(&target)
+    );
+    crate::cmd_save::run()
+}
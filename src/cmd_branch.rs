@@ -0,0 +1,85 @@
+use crate::cli::BranchArgs;
+use crate::gitwrap;
+use crate::paths;
+use crate::settings;
+use anyhow::{bail, Result};
+use std::fs;
+
+/// Branch set with 'ralf connect --branch' or 'ralf branch', if any. Kept in
+/// the settings rather than the repo so it survives reconnects; connecting to
+/// a different repo drops it.
+pub fn tracked_branch() -> Option<String> {
+    settings::value("branch")
+}
+
+pub fn set_tracked_branch(branch: &str) -> Result<()> {
+    settings::set("branch", branch)?;
+    // The settings file replaces the old branch file
+    let _ = fs::remove_file(paths::config_dir().join("branch"));
+    Ok(())
+}
+
+/// Stop following a branch, e.g. for a repo the setting was not made for.
+pub fn clear_tracked_branch() -> Result<()> {
+    settings::unset("branch")?;
+    let _ = fs::remove_file(paths::config_dir().join("branch"));
+    Ok(())
+}
+
+/// Check out the tracked branch when the repo has drifted to another one,
+/// so download and upload never work on the wrong alias set.
+pub fn ensure_tracked(p: &paths::Paths) -> Result<()> {
+    let Some(branch) = tracked_branch() else {
+        return Ok(());
+    };
+    if gitwrap::current_branch(&p.repo_path).as_deref() != Some(branch.as_str()) {
+        println!("Switching to branch {}", branch);
+        gitwrap::switch_branch(&p.repo_path, &branch, false)?;
+    }
+    Ok(())
+}
+
+pub fn run(args: BranchArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let repo = &p.repo_path;
+    if !repo.join(".git").exists() {
+        bail!("{} is not a git repository", paths::friendly(repo));
+    }
+
+    let Some(name) = args.name else {
+        let current = gitwrap::current_branch(repo);
+        match &current {
+            Some(b) => println!("On branch {}", b),
+            None => println!("Not on a branch (detached HEAD)"),
+        }
+        if let Some(t) = tracked_branch().filter(|t| current.as_ref() != Some(t)) {
+            println!("ralf tracks {}; download and upload will switch to it", t);
+        }
+        println!("Branches:");
+        for b in gitwrap::branches(repo)? {
+            let mark = if current.as_ref() == Some(&b) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{} {}", mark, b);
+        }
+        return Ok(());
+    };
+
+    if gitwrap::current_branch(repo).as_deref() == Some(name.as_str()) {
+        println!("Already on branch {}", name);
+    } else {
+        if !args.create && !gitwrap::branches(repo)?.contains(&name) {
+            bail!(
+                "no branch '{}' (use --create to start one from the current branch)",
+                name
+            );
+        }
+        gitwrap::switch_branch(repo, &name, args.create)?;
+        println!("Switched to branch {}", name);
+    }
+    set_tracked_branch(&name)?;
+    crate::cmd_save::run()?;
+    Ok(())
+}
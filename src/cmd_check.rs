@@ -0,0 +1,24 @@
+use crate::config_merge;
+use crate::paths;
+use crate::shadow;
+use anyhow::Result;
+
+pub fn run() -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    // Surfaces directive errors (unknown @sudo/@root values) as well
+    config_merge::resolve_privilege(&p)?;
+    let blocks = config_merge::load_and_merge_raw(&p)?;
+
+    let shadows = shadow::find_shadows(&blocks);
+    if shadows.is_empty() {
+        println!("No problems found in {} aliases", blocks.len());
+        return Ok(());
+    }
+    for s in &shadows {
+        println!("warning: {}", s.describe());
+    }
+    println!();
+    println!("Add '@shadow-ok' to mark an override as intended, for example:");
+    println!("  ls: @shadow-ok ls --color=auto");
+    std::process::exit(1);
+}
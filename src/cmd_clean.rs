@@ -6,12 +6,8 @@ pub fn run(purge: bool) -> Result<()> {
     let rc = crate::paths::env_rc_file();
     let aliases = crate::paths::env_aliases_file();
 
-    // Capture repo path before we delete rc
-    let mut repo: Option<PathBuf> = fs::read_to_string(&rc)
-        .ok()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .map(PathBuf::from);
+    // Capture repo path before we delete rc and settings
+    let mut repo: Option<PathBuf> = crate::settings::value("repo").map(PathBuf::from);
 
     if repo.is_none() {
         if let Ok(cwd) = std::env::current_dir() {
@@ -58,9 +54,9 @@ pub fn run(purge: bool) -> Result<()> {
         }
     }
 
-    // Remove unified files
+    // Remove unified files and settings
     let cfg = crate::paths::config_dir();
-    for name in ["aliases.sh", "aliases.fish"] {
+    for name in ["aliases.sh", "aliases.fish", "config.toml"] {
         let p = cfg.join(name);
         if p.exists() {
             let _ = fs::remove_file(&p);
@@ -0,0 +1,91 @@
+use crate::cli::{ConfigAction, ConfigArgs};
+use crate::paths;
+use crate::settings::{self, Source, SETTINGS};
+use anyhow::{bail, Result};
+use serde_json::json;
+
+pub fn run(args: ConfigArgs) -> Result<()> {
+    match args.action.unwrap_or(ConfigAction::List { json: false }) {
+        ConfigAction::List { json } => list(json),
+        ConfigAction::Get { key } => get(&key),
+        ConfigAction::Set { key, value } => set(&key, &value),
+        ConfigAction::Unset { key } => unset(&key),
+    }
+}
+
+fn known(key: &str) -> Result<()> {
+    if settings::find(key).is_none() {
+        bail!("unknown setting '{}' (see 'ralf config list')", key);
+    }
+    Ok(())
+}
+
+fn list(json_out: bool) -> Result<()> {
+    if json_out {
+        let list: Vec<_> = SETTINGS
+            .iter()
+            .map(|s| {
+                let found = settings::lookup(s.key);
+                json!({
+                    "key": s.key,
+                    "value": found.as_ref().map(|(v, _)| v),
+                    "source": found.as_ref().map(|(_, from)| from.describe()),
+                    "about": s.about,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+        return Ok(());
+    }
+
+    println!("Settings in {}:", paths::friendly(&settings::path()));
+    for s in SETTINGS {
+        match settings::lookup(s.key) {
+            Some((value, Source::File)) => println!("  {:<13} {}", s.key, value),
+            Some((value, from)) => {
+                println!("  {:<13} {}  (from {})", s.key, value, from.describe())
+            }
+            None => println!("  {:<13} unset  ({})", s.key, s.about),
+        }
+    }
+    Ok(())
+}
+
+fn get(key: &str) -> Result<()> {
+    known(key)?;
+    match settings::value(key) {
+        Some(value) => println!("{}", value),
+        None => bail!("{} is not set", key),
+    }
+    Ok(())
+}
+
+fn set(key: &str, value: &str) -> Result<()> {
+    let value = settings::set(key, value)?;
+    println!("{} = {}", key, value);
+    // Say so when the stored value is not the one in effect
+    if let Some((_, from)) = settings::lookup(key) {
+        if !matches!(from, Source::File) {
+            println!("Note: {} takes precedence here", from.describe());
+        }
+    }
+    Ok(())
+}
+
+fn unset(key: &str) -> Result<()> {
+    known(key)?;
+    if settings::unset(key)? {
+        println!(
+            "Removed {} from {}",
+            key,
+            paths::friendly(&settings::path())
+        );
+    } else {
+        println!(
+            "{} is not set in {}",
+            key,
+            paths::friendly(&settings::path())
+        );
+    }
+    Ok(())
+}
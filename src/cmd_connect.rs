@@ -1,26 +1,36 @@
 use crate::cli::ConnectArgs;
 use crate::gitwrap;
 use crate::paths;
+use crate::settings;
 use crate::tui;
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
 
 pub fn run(args: ConnectArgs) -> Result<()> {
+    if let Some(kind) = args.sync.as_deref().filter(|k| *k != "git") {
+        return connect_synced(kind, &args.repo, args.dir.as_deref());
+    }
     let input = args.repo;
-    let only_user = !input.contains(':') && !input.contains('/');
     let mut alt_repo_url: Option<String> = None;
-    let mut partial_github = false;
     let mut repo_url: String;
 
-    if input.contains(':') {
-        repo_url = input; // full URL
-    } else if input.contains('/') {
-        partial_github = true;
-        repo_url = format!("{}.git", input);
-    } else {
-        partial_github = true;
-        repo_url = format!("{}/ralf-conf.git", input);
-        alt_repo_url = Some(format!("{}/alf-conf.git", input));
+    // Shorthands name a forge host and a path on it; anything else is a full URL
+    let forge = split_forge(&input);
+    let only_user = forge.as_ref().is_some_and(|(_, rest)| !rest.contains('/'));
+    let partial = forge.is_some();
+    let host = forge.as_ref().map(|(h, _)| h.clone()).unwrap_or_default();
+    match &forge {
+        None => repo_url = input, // full URL
+        Some((_, rest)) if rest.contains('/') => {
+            repo_url = format!("{}.git", rest.trim_end_matches(".git"));
+        }
+        Some((_, user)) => {
+            repo_url = format!("{}/ralf-conf.git", user);
+            alt_repo_url = Some(format!("{}/alf-conf.git", user));
+        }
     }
+    let ssh_prefix = format!("git@{}:", host);
+    let https_prefix = format!("https://{}/", host);
 
     let force_tui = args.tui
         || matches!(
@@ -29,47 +39,38 @@ pub fn run(args: ConnectArgs) -> Result<()> {
         );
 
     // Destination and rc paths
-    let cwd = std::env::current_dir()?;
-    let dir_ralf = cwd.join("ralf-conf");
-    let dir_alf = cwd.join("alf-conf");
-    let mut dest = if dir_ralf.exists() {
-        dir_ralf
-    } else if dir_alf.exists() {
-        dir_alf
-    } else {
-        let name = repo_url
-            .rsplit('/')
-            .next()
-            .unwrap_or("ralf-conf.git")
-            .trim_end_matches(".git");
-        cwd.join(name)
-    };
-    let rc_file = paths::env_rc_file();
+    let dest = destination(args.dir.as_deref(), paths::default_repo())?;
+    // The tracked branch belongs to the repo it was set for
+    let same_repo = is_current_repo(&dest);
+    let branch = args
+        .branch
+        .clone()
+        .or_else(|| same_repo.then(crate::cmd_branch::tracked_branch).flatten());
 
     // Non-interactive flags
     let accepted: bool;
     if !force_tui && args.ssh {
-        if partial_github {
-            repo_url = format!("git@github.com:{}", repo_url);
+        if partial {
+            repo_url = format!("{}{}", ssh_prefix, repo_url);
         }
         if let Some(alt) = alt_repo_url.take() {
-            alt_repo_url = Some(format!("git@github.com:{}", alt));
+            alt_repo_url = Some(format!("{}{}", ssh_prefix, alt));
         }
         println!("Connecting to {}", repo_url);
         accepted = true;
     } else if !force_tui && args.https {
-        if partial_github {
-            repo_url = format!("https://github.com/{}", repo_url);
+        if partial {
+            repo_url = format!("{}{}", https_prefix, repo_url);
         }
         if let Some(alt) = alt_repo_url.take() {
-            alt_repo_url = Some(format!("https://github.com/{}", alt));
+            alt_repo_url = Some(format!("{}{}", https_prefix, alt));
         }
         println!("Connecting to {}", repo_url);
         accepted = true;
     } else if !force_tui && args.yes {
-        if partial_github {
+        if partial {
             println!("Error: Cannot determine the full URL for the repository");
-            println!("To connect to GitHub use --ssh or --https");
+            println!("To connect to {} use --ssh or --https", host);
             println!("To connect to another repository, provide the full URL");
             std::process::exit(1);
         }
@@ -81,30 +82,34 @@ pub fn run(args: ConnectArgs) -> Result<()> {
         println!();
         if !dest.exists() {
             println!("  clone  {}", repo_url);
-            println!("     to  ./{}", dest.file_name().unwrap().to_string_lossy());
+            println!("     to  {}", paths::friendly(&dest));
             println!();
         }
-        println!("  write  {}/ralf-conf", cwd.display());
-        println!("     to  {}", rc_file.display());
+        if let Some(b) = &branch {
+            println!("  check out branch {}", b);
+            println!();
+        }
+        println!("  write  {}", dest.display());
+        println!("     to  {}", settings::repo_record().display());
         println!();
 
-        if partial_github {
-            println!("Would you like to connect to GitHub via SSH or HTTPS?");
+        if partial {
+            println!("Would you like to connect to {} via SSH or HTTPS?", host);
             println!();
-            match tui::choose_github_protocol()? {
+            match tui::choose_protocol(&host)? {
                 tui::ConnectChoice::Ssh => {
-                    repo_url = format!("git@github.com:{}", repo_url);
+                    repo_url = format!("{}{}", ssh_prefix, repo_url);
                     if let Some(alt) = alt_repo_url.take() {
-                        alt_repo_url = Some(format!("git@github.com:{}", alt));
+                        alt_repo_url = Some(format!("{}{}", ssh_prefix, alt));
                     }
                     accepted = true;
                     println!();
                     println!("Using {}", repo_url);
                 }
                 tui::ConnectChoice::Https => {
-                    repo_url = format!("https://github.com/{}", repo_url);
+                    repo_url = format!("{}{}", https_prefix, repo_url);
                     if let Some(alt) = alt_repo_url.take() {
-                        alt_repo_url = Some(format!("https://github.com/{}", alt));
+                        alt_repo_url = Some(format!("{}{}", https_prefix, alt));
                     }
                     accepted = true;
                     println!();
@@ -129,23 +134,44 @@ pub fn run(args: ConnectArgs) -> Result<()> {
     }
 
     if dest.exists() {
+        // Reuse a clone of the same repo only, never one of another
+        let origin = dest
+            .join(".git")
+            .exists()
+            .then(|| gitwrap::remote_url(&dest))
+            .flatten();
+        let wanted = [Some(&repo_url), alt_repo_url.as_ref()];
+        if !wanted
+            .into_iter()
+            .flatten()
+            .any(|url| origin.as_deref().is_some_and(|o| same_remote(o, url)))
+        {
+            bail!(
+                "{} already exists and is {}; pass --dir <path> to clone {} elsewhere, or remove the directory",
+                paths::friendly(&dest),
+                match &origin {
+                    Some(o) => format!("a clone of {}", o),
+                    None => "not a clone of it".to_string(),
+                },
+                repo_url
+            );
+        }
         println!("Skipping clone, directory already exists");
+        if let Some(b) = &branch {
+            if gitwrap::current_branch(&dest).as_deref() != Some(b.as_str()) {
+                gitwrap::switch_branch(&dest, b, false)?;
+                println!("Switched to branch {}", b);
+            }
+        }
     } else {
-        let primary = gitwrap::clone(&repo_url, &dest);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let primary = gitwrap::clone(&repo_url, &dest, branch.as_deref());
         if primary.is_err() && only_user {
             if let Some(alt) = alt_repo_url.clone() {
                 eprintln!("Primary clone failed, retrying with {}", alt);
-                // If the alt name differs (ralf-conf vs alf-conf), adjust dest
-                let alt_name = alt
-                    .rsplit('/')
-                    .next()
-                    .unwrap_or("alf-conf.git")
-                    .trim_end_matches(".git");
-                let alt_dest = cwd.join(alt_name);
-                if dest != alt_dest && !alt_dest.exists() {
-                    dest = alt_dest;
-                }
-                gitwrap::clone(&alt, &dest)?;
+                gitwrap::clone(&alt, &dest, branch.as_deref())?;
             } else {
                 primary?;
             }
@@ -154,24 +180,115 @@ pub fn run(args: ConnectArgs) -> Result<()> {
         }
     }
 
-    // If we ended up with alf-conf, migrate folder name to ralf-conf
-    if dest.file_name().map(|n| n == "alf-conf").unwrap_or(false) {
-        let new_dest = cwd.join("ralf-conf");
-        if !new_dest.exists() {
-            std::fs::rename(&dest, &new_dest)?;
-            dest = new_dest;
-        }
+    // Record the clone's location (overwriting a previous one)
+    let abs = dest.canonicalize().unwrap_or(dest.clone());
+    let stored = settings::store_repo(&abs, None)?;
+    println!("Storing location in {}", stored.display());
+    if let Some(b) = &args.branch {
+        crate::cmd_branch::set_tracked_branch(b)?;
+    } else if !same_repo {
+        crate::cmd_branch::clear_tracked_branch()?;
+    }
+
+    // Regenerate aliases
+    crate::cmd_save::run()?;
+    Ok(())
+}
+
+/// Where to clone: `--dir` (relative to the current directory), else the
+/// stable `default` so the config does not depend on where connect ran.
+fn destination(dir: Option<&str>, default: PathBuf) -> Result<PathBuf> {
+    Ok(match dir {
+        Some(d) => std::env::current_dir()?.join(shellexpand::tilde(d).into_owned()),
+        None => default,
+    })
+}
+
+/// Whether `dir` is the repo recorded now.
+fn is_current_repo(dir: &Path) -> bool {
+    let canon = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    settings::value("repo").is_some_and(|r| canon(Path::new(&r)) == canon(dir))
+}
+
+/// Whether two remote URLs name the same repo, ignoring a `.git` suffix.
+fn same_remote(a: &str, b: &str) -> bool {
+    let norm = |u: &str| {
+        let u = u.trim().trim_end_matches('/');
+        u.strip_suffix(".git").unwrap_or(u).to_string()
+    };
+    norm(a) == norm(b)
+}
+
+/// Host behind a forge name: a known forge, or any host name with a dot in it.
+pub fn forge_host(name: &str) -> Option<String> {
+    let host = match name.to_lowercase().as_str() {
+        "github" | "gh" => "github.com".to_string(),
+        "gitlab" | "gl" => "gitlab.com".to_string(),
+        "codeberg" | "cb" => "codeberg.org".to_string(),
+        "gitea" => "gitea.com".to_string(),
+        n if n.contains('.') => n.to_string(),
+        _ => return None,
+    };
+    Some(host)
+}
+
+/// Forge for shorthands without a prefix: the `forge` setting, else GitHub.
+fn default_forge() -> String {
+    settings::value("forge")
+        .and_then(|f| forge_host(&f))
+        .unwrap_or_else(|| "github.com".to_string())
+}
+
+/// (host, `user` or `user/repo`) for shorthands like `user`, `gitlab:user/repo`
+/// or `git.example.com:user`; None for full URLs and paths.
+fn split_forge(input: &str) -> Option<(String, String)> {
+    if input.contains("://") || input.contains('@') || input.starts_with(['/', '.', '~']) {
+        return None;
     }
+    let (host, rest) = match input.split_once(':') {
+        None => (default_forge(), input),
+        Some((prefix, rest)) => (forge_host(prefix)?, rest),
+    };
+    let parts: Vec<&str> = rest.split('/').collect();
+    if parts.len() > 2 || parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    Some((host, rest.to_string()))
+}
 
-    // Write "$PWD/ralf-conf" to rc file (overwrite)
-    if let Some(parent) = rc_file.parent() {
-        std::fs::create_dir_all(parent)?;
+/// Connect to a directory kept in sync without git: use it in place (`dir`)
+/// or mirror it into `--dir`, else a copy directory of its own (`copy`).
+fn connect_synced(kind: &str, input: &str, dir: Option<&str>) -> Result<()> {
+    let source = PathBuf::from(shellexpand::tilde(input).into_owned());
+    let source = source.canonicalize().unwrap_or(source);
+    if !source.join("ralf.conf").is_file() {
+        bail!("no ralf.conf in {}", source.display());
     }
-    let abs = dest.canonicalize().unwrap_or(dest.clone());
-    std::fs::write(&rc_file, format!("{}\n", abs.to_string_lossy()))?;
-    println!("Storing location in {}", rc_file.display());
+    let (repo, setting) = if kind == "dir" {
+        println!("Using {} as is", source.display());
+        (source, "dir".to_string())
+    } else {
+        let dest = destination(dir, paths::data_dir().join("copy"))?;
+        // Mirroring deletes what the source lacks, so only into a directory
+        // that is empty or already this copy
+        let current = settings::value("repo").map(PathBuf::from);
+        let in_use = std::fs::read_dir(&dest).is_ok_and(|mut d| d.next().is_some());
+        if in_use && current.as_ref() != Some(&dest) {
+            bail!(
+                "{} is not empty; pass --dir <path> to copy {} elsewhere, or remove the directory",
+                paths::friendly(&dest),
+                source.display()
+            );
+        }
+        println!("Copying {} to {}", source.display(), dest.display());
+        let synced = crate::sync::synced_files(&dest);
+        crate::sync::mirror(&source, &dest, "ralf.conf", &synced)?;
+        crate::sync::record_synced(&dest, &source, "ralf.conf")?;
+        (dest, format!("copy:{}", source.display()))
+    };
 
-    // Regenerate aliases
+    let stored = settings::store_repo(&repo, Some(&setting))?;
+    println!("Storing location in {}", stored.display());
     crate::cmd_save::run()?;
     Ok(())
 }
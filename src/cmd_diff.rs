@@ -0,0 +1,156 @@
+use crate::cli::DiffArgs;
+use crate::config_merge;
+use crate::domain::{diff_blocks, AliasBlock, Change, ChangeKind};
+use crate::generator::{self, Shell};
+use crate::paths;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+pub fn run(args: DiffArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let current = config_merge::resolve_machine_id(&p);
+
+    let (old_label, old, new_label, new) = if args.generated {
+        let shell = generator::target_shell(&p.aliases_file);
+        let on_disk = std::fs::read_to_string(&p.aliases_file).unwrap_or_default();
+        let text = config_merge::load_and_merge(&p)?;
+        let opts = generator::options_for(&p)?;
+        let fresh = match shell {
+            Shell::Sh => generator::generate_config_sh_from_text(&text, &opts)?,
+            Shell::Fish => generator::generate_config_fish_from_text(&text, &opts)?,
+        };
+        (
+            paths::friendly(&p.aliases_file),
+            generator::parse_generated(&on_disk, shell),
+            "what 'ralf save' would write".to_string(),
+            generator::parse_generated(&fresh, shell),
+        )
+    } else if args.rev.is_some() || args.machine.is_empty() {
+        if args.machine.len() > 1 {
+            bail!("--rev compares one machine against itself; pass at most one --machine");
+        }
+        let rev = args.rev.as_deref().unwrap_or("HEAD");
+        let machine = args.machine.first().unwrap_or(&current).clone();
+        if !config_merge::valid_machine_id(&machine) {
+            bail!("invalid machine id '{}'", machine);
+        }
+        crate::gitwrap::verify_rev(&p.repo_path, rev)?;
+        let layers = layers_for(&p, &machine);
+        let mut sources = Vec::new();
+        for layer in &layers {
+            let rel = layer.strip_prefix(&p.repo_path).unwrap_or(layer);
+            if let Some(text) = crate::gitwrap::show_file(&p.repo_path, rev, rel)? {
+                sources.push((layer.clone(), text));
+            }
+        }
+        let existing: Vec<PathBuf> = layers.into_iter().filter(|l| l.exists()).collect();
+        // Say so when this machine's local overlay is left out of the comparison
+        let local = config_merge::machine_layers(&p, &machine)
+            .into_iter()
+            .find(|l| config_merge::layer_name(&p, l) == "local");
+        let new_label = match local {
+            Some(l) => format!(
+                "working tree ({}, without {})",
+                machine,
+                l.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            None => format!("working tree ({})", machine),
+        };
+        (
+            format!("{} ({})", rev, machine),
+            config_merge::merge_sources(&sources)?,
+            new_label,
+            config_merge::merge_model(&existing)?,
+        )
+    } else {
+        let (a, b) = match args.machine.as_slice() {
+            [b] => (current.clone(), b.clone()),
+            [a, b] => (a.clone(), b.clone()),
+            _ => bail!("pass one --machine to compare with this machine, or two to compare them"),
+        };
+        (
+            format!("machine {}", a),
+            model_for(&p, &a)?,
+            format!("machine {}", b),
+            model_for(&p, &b)?,
+        )
+    };
+
+    let changes = diff_blocks(&old, &new);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+    println!("--- {}", old_label);
+    println!("+++ {}", new_label);
+    if changes.is_empty() {
+        println!("No differences");
+    } else {
+        print_changes(&changes, "");
+    }
+    Ok(())
+}
+
+/// Versioned layers of a machine, whether or not they exist on disk: the base
+/// config, its group overlays (by the working tree's rules) and its machine
+/// overlay. The local overlay belongs to this checkout rather than to any
+/// revision, so it is left out.
+fn layers_for(p: &paths::Paths, machine: &str) -> Vec<PathBuf> {
+    let mut layers = vec![p.config_file.clone()];
+    for group in config_merge::groups(p, machine) {
+        layers.push(config_merge::group_path(p, &group));
+    }
+    layers.push(config_merge::overlay_paths(p, machine).0);
+    layers
+}
+
+fn model_for(p: &paths::Paths, machine: &str) -> Result<Vec<AliasBlock>> {
+    if !config_merge::valid_machine_id(machine) {
+        bail!("invalid machine id '{}'", machine);
+    }
+    config_merge::merge_model(&config_merge::machine_layers(p, machine))
+}
+
+/// One `+`/`-`/`~` line per change (two for a change), each prefixed with `indent`.
+pub fn print_changes(changes: &[Change], indent: &str) {
+    let width = changes.iter().map(|c| c.name().len()).max().unwrap_or(0);
+    for c in changes {
+        let one_line = |s: &Option<String>| s.clone().unwrap_or_default().replace('\n', "; ");
+        match c.kind {
+            ChangeKind::Added => {
+                println!(
+                    "{}+ {:<width$}  {}",
+                    indent,
+                    c.name(),
+                    one_line(&c.new),
+                    width = width
+                )
+            }
+            ChangeKind::Removed => {
+                println!(
+                    "{}- {:<width$}  {}",
+                    indent,
+                    c.name(),
+                    one_line(&c.old),
+                    width = width
+                )
+            }
+            ChangeKind::Changed => {
+                println!(
+                    "{}~ {:<width$}  {}",
+                    indent,
+                    c.name(),
+                    one_line(&c.old),
+                    width = width
+                );
+                println!(
+                    "{}  {:<width$}  {}",
+                    indent,
+                    "",
+                    one_line(&c.new),
+                    width = width
+                );
+            }
+        }
+    }
+}
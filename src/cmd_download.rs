@@ -1,18 +1,128 @@
+use crate::cli::DownloadArgs;
+use crate::domain::{merge3, take_theirs, Conflict};
 use crate::paths;
-use anyhow::Result;
-
-pub fn run() -> Result<()> {
-    let rc = paths::env_rc_file();
-    if !rc.exists() {
-        println!("Cannot find {}", rc.display());
-        println!("Please connect ralf to a repository first");
-        std::process::exit(1);
-    }
+use anyhow::{bail, Result};
+use std::io::IsTerminal;
+use std::path::Path;
 
+pub fn run(args: DownloadArgs) -> Result<()> {
+    crate::settings::require_repo();
     let p = paths::find_config_or_exit()?;
+    crate::sync::backend()?.download(&p, &args)?;
+    crate::cmd_save::run()?;
+    Ok(())
+}
+
+/// `git pull`, merging conflicting alias configs alias by alias.
+pub fn git_download(p: &paths::Paths, args: &DownloadArgs) -> Result<()> {
     println!("Pulling from repository to {}", p.repo_path.display());
-    crate::gitwrap::pull(&p.repo_path)?;
+    crate::cmd_branch::ensure_tracked(p)?;
+    let conflicted = crate::gitwrap::pull(&p.repo_path)?;
+    if !conflicted.is_empty() {
+        if let Err(e) = resolve(p, &conflicted, args) {
+            crate::gitwrap::merge_abort(&p.repo_path)?;
+            return Err(e.context("download aborted; your config is unchanged"));
+        }
+    }
     crate::gitwrap::ensure_ralf_gitignore(&p.repo_path)?;
-    crate::cmd_save::run()?;
     Ok(())
 }
+
+/// Merge each conflicted layer alias by alias, settle what both sides changed,
+/// then conclude the merge. Nothing is written until every conflict is settled.
+fn resolve(p: &paths::Paths, files: &[String], args: &DownloadArgs) -> Result<()> {
+    let repo = &p.repo_path;
+    let interactive = std::io::stdin().is_terminal();
+    let mut merged = Vec::new();
+    let mut unresolved = Vec::new();
+    for file in files {
+        if !is_layer(p, file) {
+            bail!(
+                "{} conflicts and is not an alias config; resolve it with git in {}",
+                file,
+                repo.display()
+            );
+        }
+        let stage = |n: &str| crate::gitwrap::show_file(repo, n, Path::new(file));
+        let (Some(ours), Some(theirs)) = (stage(":2")?, stage(":3")?) else {
+            bail!(
+                "{} was deleted on one side; resolve it with git in {}",
+                file,
+                repo.display()
+            );
+        };
+        let base = stage(":1")?.unwrap_or_default();
+        let (mut text, conflicts) = merge3(&base, &ours, &theirs)
+            .map_err(|e| anyhow::anyhow!("cannot merge {}: {}", file, e))?;
+        for c in &conflicts {
+            let theirs_wins = if args.ours {
+                false
+            } else if args.theirs {
+                true
+            } else if interactive {
+                ask(file, c)?
+            } else {
+                unresolved.push(format!("  {}: {}", file, describe(c)));
+                continue;
+            };
+            if theirs_wins {
+                text = take_theirs(&text, c)?;
+            }
+        }
+        merged.push((file.clone(), text, conflicts.len()));
+    }
+    if !unresolved.is_empty() {
+        bail!(
+            "aliases changed on both sides:\n{}\nrerun with --ours or --theirs, or interactively",
+            unresolved.join("\n")
+        );
+    }
+
+    for (file, text, conflicts) in &merged {
+        std::fs::write(repo.join(file), text)?;
+        if *conflicts == 0 {
+            println!("Merged {}", file);
+        } else {
+            println!("Merged {} ({} conflict(s) resolved)", file, conflicts);
+        }
+    }
+    crate::gitwrap::commit_merge(repo, files)
+}
+
+/// The base config, a machine overlay or a group overlay, as a repo-relative path.
+fn is_layer(p: &paths::Paths, file: &str) -> bool {
+    let base = p.config_file.strip_prefix(&p.repo_path).ok();
+    let overlay = |f: &str| f.ends_with(".conf") && !f.contains('/');
+    base == Some(Path::new(file))
+        || file.strip_prefix("machines/groups/").is_some_and(overlay)
+        || file.strip_prefix("machines/").is_some_and(overlay)
+}
+
+fn describe(c: &Conflict) -> String {
+    format!(
+        "{} (was {}, ours {}, theirs {})",
+        c.name(),
+        show(&c.base),
+        show(&c.ours),
+        show(&c.theirs)
+    )
+}
+
+fn show(v: &Option<String>) -> String {
+    match v {
+        Some(s) => format!("'{}'", s.replace('\n', "; ")),
+        None => "removed".to_string(),
+    }
+}
+
+/// True to take their side.
+fn ask(file: &str, c: &Conflict) -> Result<bool> {
+    let title = format!("{}: {} (was {})", file, c.name(), show(&c.base));
+    let keep = format!("Keep ours: {}", show(&c.ours));
+    let take = format!("Take theirs: {}", show(&c.theirs));
+    match crate::tui::select(&title, &[&keep, &take, "Abort download"])? {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => bail!("cancelled"),
+    }
+}
@@ -1,31 +1,200 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn run(what: Option<String>) -> Result<()> {
-    if matches!(what.as_deref(), Some("machine")) {
-        let p = crate::paths::find_config_or_exit()?;
-        let mid = crate::config_merge::resolve_machine_id(&p);
-        let (machine_overlay, _) = crate::config_merge::overlay_paths(&p, &mid);
-        if let Some(parent) = machine_overlay.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        // Create empty file if missing
-        if !machine_overlay.exists() {
-            std::fs::write(&machine_overlay, b"")?;
-        }
-        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-        let status = std::process::Command::new(editor)
-            .arg(&machine_overlay)
-            .status()?;
-        if !status.success() {
-            anyhow::bail!("editor exited with non-zero status");
-        }
-        return Ok(());
+pub fn run(args: Vec<String>) -> Result<()> {
+    let p = crate::paths::find_config_or_exit()?;
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (target, line, template) = match words.as_slice() {
+        [] => (p.config_file.clone(), None, BASE_TEMPLATE.to_string()),
+        ["local"] => (
+            crate::config_merge::layer_target(&p, None, true)?,
+            None,
+            LOCAL_TEMPLATE.to_string(),
+        ),
+        ["machine", rest @ ..] if rest.len() <= 1 => {
+            let mid = match rest.first() {
+                Some(id) => id.to_string(),
+                None => crate::config_merge::resolve_machine_id(&p),
+            };
+            (
+                crate::config_merge::layer_target(&p, Some(&mid), false)?,
+                None,
+                MACHINE_TEMPLATE.replace("{id}", &mid),
+            )
+        }
+        ["group", name] => {
+            if !crate::config_merge::valid_machine_id(name) {
+                bail!("invalid group name '{}'", name);
+            }
+            (
+                crate::config_merge::group_path(&p, name),
+                None,
+                GROUP_TEMPLATE.replace("{name}", name),
+            )
+        }
+        [alias] | [alias, _] => {
+            let sub = words.get(1).copied();
+            let (_, defs) = crate::config_merge::load_and_merge_traced(&p)?;
+            let Some(def) = defs
+                .iter()
+                .rfind(|d| d.alias == *alias && d.sub.as_deref() == sub)
+            else {
+                println!(
+                    "Error: No such alias: {}{}",
+                    alias,
+                    sub.map(|s| format!(" {}", s)).unwrap_or_default()
+                );
+                std::process::exit(1);
+            };
+            (def.file.clone(), Some(def.line), String::new())
+        }
+        _ => bail!("usage: ralf edit [local | machine [<id>] | group <name> | <alias> [<sub>]]"),
+    };
+    if edit_file(&target, line, &template)? {
+        crate::cmd_save::run()?;
     }
+    Ok(())
+}
 
-    let p = crate::paths::find_config_or_exit()?;
-    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-    let status = Command::new(editor).arg(&p.config_file).status()?;
+const BASE_TEMPLATE: &str = "\
+# ralf aliases: one 'name: command' per line, subcommands indented below.
+#
+# g: git
+#   l: log --oneline
+";
+
+pub const MACHINE_TEMPLATE: &str = "\
+# Overlay for machine '{id}': aliases here override ralf.conf on that machine.
+#
+# g: hub
+#   l: log --oneline --graph
+";
+
+const GROUP_TEMPLATE: &str = "\
+# Overlay for group '{name}': aliases here override ralf.conf on machines that
+# an '@{name}: <condition>...' line in machines/rules puts in the group.
+# Machine overlays override it in turn.
+#
+# d: docker
+";
+
+const LOCAL_TEMPLATE: &str = "\
+# Local overlay: overrides every other layer, on this checkout only.
+#
+# g: git
+";
+
+/// Edit a copy of `target`, validate it when the editor exits and only then
+/// move it into place. A missing file starts out as `template`.
+/// Returns whether the file was written.
+pub fn edit_file(target: &Path, line: Option<usize>, template: &str) -> Result<bool> {
+    let exists = target.exists();
+    let original = if exists {
+        std::fs::read_to_string(target)?
+    } else {
+        template.to_string()
+    };
+    let scratch = scratch_path(target);
+    std::fs::write(&scratch, &original)?;
+    let result = edit_loop(target, &scratch, &original, line, exists);
+    let _ = std::fs::remove_file(&scratch);
+    if let Some(dir) = scratch.parent() {
+        let _ = std::fs::remove_dir(dir);
+    }
+    let Some(edited) = result? else {
+        return Ok(false);
+    };
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write next to the target and rename, so a crash never leaves half a file.
+    let staged = target.with_extension("conf.ralf-new");
+    std::fs::write(&staged, &edited)?;
+    std::fs::rename(&staged, target)
+        .with_context(|| format!("failed to replace {}", target.display()))?;
+    println!("Saved {}", target.display());
+    Ok(true)
+}
+
+fn edit_loop(
+    target: &Path,
+    scratch: &Path,
+    original: &str,
+    line: Option<usize>,
+    exists: bool,
+) -> Result<Option<String>> {
+    loop {
+        launch_editor(scratch, line)?;
+        let edited = std::fs::read_to_string(scratch)?;
+        if edited == original && exists {
+            println!("No changes to {}", target.display());
+            return Ok(None);
+        }
+        let problems = crate::domain::validate_text(&edited)?;
+        if problems.is_empty() {
+            return Ok(Some(edited));
+        }
+        for (line, msg) in &problems {
+            eprintln!("{}:{}: {}", target.display(), line, msg);
+        }
+        if !std::io::stdin().is_terminal() {
+            bail!("edit discarded; {} is unchanged", target.display());
+        }
+        let items = ["Edit again", "Discard changes"];
+        if crate::tui::select("The edited config has errors", &items)? != Some(0) {
+            println!("Discarded changes; {} is unchanged", target.display());
+            return Ok(None);
+        }
+    }
+}
+
+/// Temp copy with the same file name, so editors still pick the right syntax.
+fn scratch_path(target: &Path) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ralf-edit-{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    dir.join(target.file_name().unwrap_or_else(|| "ralf.conf".as_ref()))
+}
+
+/// The `editor` setting, then `$VISUAL`, then `$EDITOR`, then vi. The value
+/// goes through `sh` so that editors with arguments such as `code --wait` work.
+fn editor_command() -> String {
+    crate::settings::value("editor").unwrap_or_else(|| "vi".to_string())
+}
+
+/// Arguments that open `file` at `line`: `+N file` for the vi/emacs family
+/// and nano, `--goto file:N` for VS Code. Unknown editors just get the file.
+fn file_args(editor: &str, file: &Path, line: Option<usize>) -> Vec<String> {
+    let path = file.to_string_lossy().to_string();
+    let Some(n) = line else {
+        return vec![path];
+    };
+    let program = editor.split_whitespace().next().unwrap_or_default();
+    let name = Path::new(program)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match name.as_str() {
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" | "nano" | "pico" | "emacs" | "emacsclient"
+        | "micro" | "kak" | "joe" | "mg" => vec![format!("+{}", n), path],
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["--goto".to_string(), format!("{}:{}", path, n)]
+        }
+        _ => vec![path],
+    }
+}
+
+fn launch_editor(file: &Path, line: Option<usize>) -> Result<()> {
+    let editor = editor_command();
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg("sh")
+        .args(file_args(&editor, file, line))
+        .status()
+        .with_context(|| format!("failed to start editor '{}'", editor))?;
     if !status.success() {
         bail!("editor exited with non-zero status");
     }
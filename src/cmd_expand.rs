@@ -0,0 +1,121 @@
+use crate::domain::AliasBlock;
+use crate::generator::{self, Shell};
+use anyhow::{bail, Result};
+
+pub fn run(mut words: Vec<String>, mut shell: Option<String>) -> Result<()> {
+    // `--shell <sh>` after the alias words ends up among them; take it back
+    if shell.is_none() {
+        let n = words.len();
+        if n > 2 && words[n - 2] == "--shell" {
+            shell = words.pop();
+            words.pop();
+        } else if let Some(sh) = words.last().and_then(|w| w.strip_prefix("--shell=")) {
+            shell = Some(sh.to_string());
+            words.pop();
+        }
+    }
+    let shells = match shell.as_deref() {
+        None => vec![Shell::Sh, Shell::Fish],
+        Some("bash" | "zsh" | "sh") => vec![Shell::Sh],
+        Some("fish") => vec![Shell::Fish],
+        Some(other) => bail!("unknown shell '{}' (expected bash, zsh or fish)", other),
+    };
+    let mut words = words.into_iter();
+    let alias = words.next().unwrap_or_default();
+    let args: Vec<String> = words.collect();
+
+    let p = crate::paths::find_config_or_exit()?;
+    let blocks = crate::config_merge::load_and_merge_model(&p)?;
+    let Some(block) = blocks.iter().find(|b| b.name == alias) else {
+        println!("Error: No such alias: {}", alias);
+        std::process::exit(1);
+    };
+
+    // The generated function matches its first argument against the subs.
+    let sub = args
+        .first()
+        .filter(|a| block.subs.iter().any(|(n, _)| n == *a))
+        .cloned();
+    let rest = if sub.is_some() { &args[1..] } else { &args[..] };
+
+    println!(
+        "$ {}",
+        quote_all(Shell::Sh, &[vec![alias.clone()], args.clone()].concat())
+    );
+    for shell in shells {
+        println!();
+        print_expansion(block, sub.as_deref(), rest, shell)?;
+    }
+    Ok(())
+}
+
+fn print_expansion(
+    block: &AliasBlock,
+    sub: Option<&str>,
+    args: &[String],
+    shell: Shell,
+) -> Result<()> {
+    println!("{}", shell.label());
+    let Some(line) = generator::expansion(block, sub, shell)? else {
+        println!("  not generated ('{}' is reserved in fish)", block.name);
+        return Ok(());
+    };
+    let branch = match (shell, sub) {
+        (Shell::Sh, Some(s)) => format!("{})", s),
+        (Shell::Fish, Some(s)) => format!("case {}", s),
+        (Shell::Sh, None) if !block.subs.is_empty() => "*)".to_string(),
+        (Shell::Fish, None) if !block.subs.is_empty() => "case '*'".to_string(),
+        _ => "function body".to_string(),
+    };
+    println!("  branch:  {}", branch);
+
+    // Arguments replace the trailing forwarder; commands that use them
+    // explicitly get them as positional parameters instead.
+    let forwarder = match (shell, sub) {
+        (Shell::Sh, _) => " \"$@\"",
+        (Shell::Fish, Some(_)) => " $rest",
+        (Shell::Fish, None) => " $argv",
+    };
+    let quoted = quote_all(shell, args);
+    match line.strip_suffix(forwarder) {
+        Some(head) if quoted.is_empty() => println!("  runs:    {}", head),
+        Some(head) => println!("  runs:    {} {}", head, quoted),
+        None => {
+            println!("  runs:    {}", line);
+            if !args.is_empty() {
+                let name = match (shell, sub) {
+                    (Shell::Sh, _) => "$@",
+                    (Shell::Fish, Some(_)) => "$rest",
+                    (Shell::Fish, None) => "$argv",
+                };
+                println!("  with:    {} = {}", name, quoted);
+            }
+        }
+    }
+    if line.contains("__ralf_sudo ") {
+        println!(
+            "  note:    __ralf_sudo escalates according to the machine's @sudo/@root settings"
+        );
+    }
+    Ok(())
+}
+
+fn quote_all(shell: Shell, args: &[String]) -> String {
+    args.iter()
+        .map(|a| quote(shell, a))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote(shell: Shell, s: &str) -> String {
+    let plain = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        return s.to_string();
+    }
+    match shell {
+        Shell::Sh => format!("'{}'", s.replace('\'', "'\"'\"'")),
+        Shell::Fish => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+    }
+}
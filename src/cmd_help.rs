@@ -14,35 +14,143 @@ DESCRIPTION
   and works natively in bash and zsh.
 
 COMMANDS
-  connect   Connect this machine to a config repo (clone and set rc)
-  download  Pull latest changes from the repo and regenerate aliases
-  upload    Commit and push changes in the repo
+  connect   Connect this machine to a config repo: clone it to the data dir (or
+            '--dir <path>') and record it in config.toml. Besides full URLs
+            it takes 'user' or 'user/repo' on the default forge, or
+            prefixed with github:, gitlab:, codeberg:, gitea: or a host name;
+            '--branch <name>' follows another branch, e.g. a work alias set;
+            '--sync dir <path>' uses a Syncthing/NFS directory in place and
+            '--sync copy <path>' mirrors one into the data dir's copy/,
+            without git
+  new       Start a config repo ('ralf new [<dir>]', default the data dir): a
+            starter ralf.conf, this machine's overlay and .gitignore, 'git init'
+            and '--remote <url>' as origin; then connects to it
+  download  Pull latest changes from the repo and regenerate aliases; conflicting
+            configs are merged alias by alias, and aliases changed on both sides
+            are asked about or settled with '--ours'/'--theirs'
+  upload    Commit and push the config, machine overlays and rules; the message
+            summarises alias changes unless '-m' is given, '--all' stages everything
+  status    Show uncommitted changes by layer, commits ahead/behind the upstream
+            ('--fetch' first), stale aliases files and whether this shell loaded
+            the latest save ('--json')
+  branch    Show the repo's branches, or switch the one ralf follows and
+            regenerate; '--create' starts a new branch from the current one
+  history   List the generations 'save' kept, with the aliases each one changed
+  rollback  Restore a kept generation ('ralf rollback [<n>]', default the one
+            before the one in use); the config itself is left alone
+  config    List ralf's settings and where each comes from; 'get <key>',
+            'set <key> <value>' and 'unset <key>' manage config.toml
   generate  Print the generated aliases to stdout
   save      Write the generated aliases to your aliases file
-  edit      Edit base config; 'ralf edit machine' edits machine overlay
-  which     Show the command behind an alias (and optional subcommand)
-  machine   Show or set current machine id used for overlays
+  add       Add an alias or sub ('--machine <id>'/'--local' target an overlay)
+  set       Change an alias or sub, adding it if missing
+  rm        Remove an alias or sub together with its comment lines
+  edit      Edit base config; 'local', 'machine [<id>]', 'group <name>' edit
+            an overlay and '<alias> [<sub>]' opens the file that defines it at its line.
+            Edits are checked before they replace the file, then saved
+  which     Show the command behind an alias; '--trace' shows which layer set it
+  run       Run an alias without sourcing anything (for cron, CI and 'ssh host')
+  expand    Show the command line 'ralf expand -- g l -5' runs in bash/zsh and fish;
+            '--shell <sh>' before the alias or at the end limits it to one shell
+  machine   Show or set current machine id used for overlays; 'list', 'show [<id>]',
+            'new <id> [--from <id>]', 'rename <old> <new>' and 'rm <id>' manage them
   clean     Remove init stubs and generated files; '--purge' also deletes repo dir
   info      Show environment, paths, files, and git remote
-  upgrade   Placeholder for self-update (not implemented yet)
+  list      List effective aliases; '--layer', '--prefix' and '--json' filter/format
+  search    Fuzzy-search alias names, commands and descriptions ('--json')
+  diff      Compare merged aliases: '--machine A [--machine B]', '--rev <rev>'
+            (default HEAD) or '--generated' (aliases file vs a fresh save)
+  check     Report aliases that shadow shell keywords, builtins or commands
+  stats     Report most-used, never-used and stale aliases ('--enable' to opt in)
+  upgrade   Install the latest release from the manifest at '--manifest' or
+            the upgrade_url setting, checking its sha256 and keeping the old
+            binary as ralf.bak; pre-releases are skipped; '--check' only reports
   help      Show this page; 'ralf help [command]' shows command help
 
 FILES
+  ~/.config/ralf/config.toml
+      Settings as 'key = "value"' lines: repo, sync, branch, machine,
+      aliases_file, theme, editor, sudo, root, forge and upgrade_url (see
+      'ralf config').
+      Environment variables override a setting; older files are read when
+      it is not set
   ~/.ralfrc or ~/.alfrc
-      Path to the repository (written by 'ralf connect')
+      Older home of the repository path (a single line); still read, and
+      written by 'ralf connect' when RALF_RC_FILE names one
+  ~/.local/share/ralf/repo ($XDG_DATA_HOME/ralf/repo)
+      Where 'ralf connect' clones unless given '--dir'; a clone of another
+      repo there is kept and connect stops. '--sync copy' mirrors into
+      copy/ next to it.
+      The config is looked up in this order: the rc file named by
+      RALF_RC_FILE when it records a repo, then the current directory and
+      its parents (ralf.conf, or one in a ralf-conf subdirectory), then the
+      repo setting (or ~/.ralfrc), then this default.
   <repo>/ralf.conf (or alf.conf)
       Base configuration file with aliases and subcommands
+  <repo>/machines/groups/<group>.conf
+      Group overlay, applied on top of base config on the machines in the
+      group, below their machine overlay
   <repo>/machines/<machine>.conf
       Machine overlay, applied on top of base config
+  <repo>/machines/rules
+      Picks the machine id when no marker or RALF_MACHINE is set: lines of
+      '<id>: <condition>...' such as 'laptop: hostname=dhcp-* user=me'.
+      Conditions: hostname, machine-id, os, user (globs) and env:NAME[=glob].
+      The first rule whose conditions all hold wins; see 'ralf machine --explain'.
+      '@<group>: <condition>...' lines put the machine in a group; all that
+      hold apply, and they may also test the machine id with machine=<glob>
   <repo>/ralf.local.conf
       Local overlay (not shared), applied last
+  ~/.config/ralf/history/<generation>/
+      The last 10 generations of the aliases files, with the config commit and
+      machine id they were generated from
+  ~/.config/ralf/usage.log
+      Alias usage log written by the generated functions after
+      'ralf stats --enable' (local only, never committed)
+
+DIRECTIVES
+  @sudo: sudo|doas|run0|none
+      Escalation tool used by '__ralf_sudo' for aliases starting with
+      'sudo '. Usually set in a machine overlay; the last layer wins.
+      'none' runs the command as-is (for machines where you are root).
+  @root: auto|always|never
+      How '@root' aliases run on this machine: escalate unless already
+      root (default), always escalate, or never escalate. 'auto' also
+      applies to aliases written with a literal 'sudo ': as root they run
+      the command directly, unless it starts with an option ('sudo -u bob').
+
+DESCRIPTIONS
+  # Show the last ten commits
+  l: log -10
+      Comment lines directly above an alias or subcommand describe it in
+      'ralf list' and 'ralf search'.
+
+ANNOTATIONS
+  upd: @root apt update
+      Mark an alias or subcommand as needing root. The base config stays
+      the same everywhere; each machine's '@root:' policy decides.
+  ls: @shadow-ok ls --color=auto
+      Mark an alias that deliberately shadows a builtin or command, so
+      'ralf check' and 'ralf save' stop warning about it.
 
 ENVIRONMENT
   ralf_RC_FILE / ALF_RC_FILE      Path to rc file (repo location)
   ralf_ALIASES_FILE / ALF_ALIASES_FILE
                                   Path to write the generated aliases
-  ralf_MACHINE                    Machine id override
+                                  (overrides the aliases_file setting)
+  ralf_MACHINE                    Machine id override (overrides the setting)
+  RALF_THEME                      TUI theme (overrides the theme setting)
+  XDG_DATA_HOME                   Base for the default clone ($XDG_DATA_HOME/ralf/repo)
   RALF_TUI_FORCE                  '1/true/yes/on' to force TUI in connect
+  RALF_FORGE                      Forge for unprefixed connect shorthands
+                                  (github, gitlab, codeberg, gitea or a host;
+                                  overrides the forge setting)
+  RALF_UPGRADE_URL                Release manifest for 'ralf upgrade' (JSON with
+                                  version and per-platform url and sha256)
+  RALF_SUDO_NO_PROMPT             Fail instead of prompting for a password
+  VISUAL / EDITOR                 Editor for 'ralf edit' when the editor
+                                  setting is unset (may include args)
+  RALF_GENERATION                 Set by the aliases file to the save it came from
 
 SHELLS
   Works in bash and zsh. For zsh, we initialize compinit and bashcompinit
@@ -52,9 +160,27 @@ EXAMPLES
   ralf help
   ralf help connect
   ralf connect you --https
+  ralf new --remote git@github.com:you/ralf-conf.git
+  ralf connect gitlab:you/dotfiles --ssh
   ralf save
   ralf which g l
+  ralf which g l --trace
+  ralf run g l --oneline -5
+  ralf expand --shell fish -- g l --oneline -5
+  ralf add g l 'log --oneline'
+  ralf set g l 'log --graph' --machine web1
+  ralf rm g l
   ralf edit machine
+  ralf edit group servers
+  ralf machine new web2 --from web1
+  ralf diff --machine web1 --machine web2
+  ralf edit g l
+  ralf status --fetch
+  ralf branch work
+  ralf connect /mnt/share/ralf-conf --sync copy
+  ralf history
+  ralf rollback
+  ralf config set editor "code --wait"
 "#;
 
 pub fn run(topic: Option<String>) -> Result<()> {
@@ -73,8 +199,11 @@ pub fn run(topic: Option<String>) -> Result<()> {
             "u" | "push" => "upload",
             "g" => "generate",
             "s" => "save",
+            "st" => "status",
             "e" => "edit",
             "w" => "which",
+            "r" => "run",
+            "x" => "expand",
             "m" => "machine",
             "h" => "help",
             _ => "",
@@ -0,0 +1,122 @@
+use crate::generator::{self, Shell};
+use crate::history::{self, Entry};
+use crate::paths;
+use anyhow::{bail, Result};
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Index of the generation the aliases file holds now, if it is still kept.
+fn in_use(entries: &[Entry]) -> Option<usize> {
+    let on_disk = std::fs::read_to_string(paths::env_aliases_file()).ok()?;
+    let generation = generator::generation(&on_disk);
+    entries.iter().position(|e| {
+        generation.as_deref() == Some(e.meta.generation.as_str())
+            || history::unstamped(&e.compat()) == history::unstamped(&on_disk)
+    })
+}
+
+/// What changed from the generation before `i`, None for the oldest kept.
+fn changes_at(entries: &[Entry], i: usize) -> Option<Vec<crate::domain::Change>> {
+    let older = entries.get(i + 1)?;
+    Some(crate::domain::diff_blocks(
+        &generator::parse_generated(&older.sh(), Shell::Sh),
+        &generator::parse_generated(&entries[i].sh(), Shell::Sh),
+    ))
+}
+
+pub fn run(json_out: bool) -> Result<()> {
+    let entries = history::entries();
+    let current = in_use(&entries);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    if json_out {
+        let list: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                json!({
+                    "index": i,
+                    "generation": e.meta.generation,
+                    "saved": e.meta.saved,
+                    "commit": e.meta.commit,
+                    "machine": e.meta.machine,
+                    "in_use": current == Some(i),
+                    "changes": changes_at(&entries, i),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No generations kept yet; 'ralf save' keeps one each time aliases change");
+        return Ok(());
+    }
+    println!("Kept generations, newest first ('ralf rollback <n>' restores one):");
+    for (i, e) in entries.iter().enumerate() {
+        println!(
+            "{:>3}  {:<12}  machine {}{}{}",
+            i,
+            history::ago(e.meta.saved, now),
+            e.meta.machine,
+            e.meta
+                .commit
+                .as_ref()
+                .map(|c| format!(", commit {}", c))
+                .unwrap_or_default(),
+            if current == Some(i) { "  (in use)" } else { "" }
+        );
+        match changes_at(&entries, i) {
+            None => println!("       (oldest kept)"),
+            Some(c) if c.is_empty() => println!("       (same aliases, different output)"),
+            Some(c) => crate::cmd_diff::print_changes(&c, "       "),
+        }
+    }
+    Ok(())
+}
+
+pub fn rollback(n: Option<usize>) -> Result<()> {
+    let entries = history::entries();
+    if entries.is_empty() {
+        bail!("no generations kept yet");
+    }
+    // Without N, step back from whatever is in use now
+    let index = n.unwrap_or_else(|| in_use(&entries).map_or(1, |i| i + 1));
+    let Some(entry) = entries.get(index) else {
+        bail!(
+            "no generation {}; {} kept (see 'ralf history')",
+            index,
+            entries.len()
+        );
+    };
+    let written = history::restore(entry)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    println!(
+        "Restored generation {} from {} (machine {}{})",
+        index,
+        history::ago(entry.meta.saved, now),
+        entry.meta.machine,
+        entry
+            .meta
+            .commit
+            .as_ref()
+            .map(|c| format!(", commit {}", c))
+            .unwrap_or_default()
+    );
+    for f in &written {
+        println!("- {}", paths::friendly(f));
+    }
+    println!(
+        "The config is unchanged; the next 'ralf save' or 'ralf download' regenerates from it."
+    );
+    println!("To apply the restored aliases to the current session, run:");
+    println!("$ source {}", paths::friendly(&entry.meta.compat_path));
+    Ok(())
+}
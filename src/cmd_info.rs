@@ -24,7 +24,10 @@ pub fn run() -> Result<()> {
 
     // ralfrc content / aliases status
     let ralfrc_content = if p.rc_file.exists() {
-        format!("exists with '{}'", fs::read_to_string(&p.rc_file)?.trim())
+        format!(
+            "exists with '{}'",
+            crate::paths::rc_repo(&fs::read_to_string(&p.rc_file)?)
+        )
     } else {
         "does not exist".into()
     };
@@ -76,6 +79,11 @@ pub fn run() -> Result<()> {
     println!("  aliases path:      {}", p.aliases_file.display());
     println!("  repo path:         {}", p.repo_path.display());
     println!("  config path:       {}", p.config_file.display());
+    println!("  settings path:     {}", crate::settings::path().display());
+    println!(
+        "  sync:              {}",
+        crate::settings::value("sync").unwrap_or_else(|| "git".into())
+    );
     println!();
 
     println!("Files:");
@@ -0,0 +1,119 @@
+use crate::cli::ListArgs;
+use crate::config_merge;
+use crate::paths;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// One invocable alias or subcommand of the merged config.
+#[derive(Serialize, Debug)]
+pub struct Entry {
+    pub alias: String,
+    pub sub: Option<String>,
+    pub command: String,
+    pub description: Option<String>,
+    /// Layer of the effective definition: base, group, machine or local
+    pub layer: &'static str,
+    pub file: String,
+    pub line: usize,
+}
+
+impl Entry {
+    pub fn name(&self) -> String {
+        match &self.sub {
+            Some(s) => format!("{} {}", self.alias, s),
+            None => self.alias.clone(),
+        }
+    }
+}
+
+/// All entries in config order, each attributed to the last layer that defined it.
+pub fn entries(p: &paths::Paths) -> Result<Vec<Entry>> {
+    let machine = config_merge::resolve_machine_id(p);
+    entries_from(p, &config_merge::layer_files(p, &machine))
+}
+
+/// Like `entries`, for an explicit list of layers.
+pub fn entries_from(p: &paths::Paths, layers: &[PathBuf]) -> Result<Vec<Entry>> {
+    let (blocks, defs) = config_merge::merge_traced(layers)?;
+    let mut out = Vec::new();
+    for b in &blocks {
+        let names = std::iter::once(None).chain(b.subs.iter().map(|(n, _)| Some(n.as_str())));
+        for sub in names {
+            let mut matching = defs
+                .iter()
+                .rev()
+                .filter(|d| d.alias == b.name && d.sub.as_deref() == sub);
+            let Some(def) = matching.next() else {
+                continue;
+            };
+            // An overlay that only changes the command keeps the base description
+            let description = def
+                .description
+                .clone()
+                .or_else(|| matching.find_map(|d| d.description.clone()));
+            out.push(Entry {
+                alias: b.name.clone(),
+                sub: sub.map(str::to_string),
+                command: b.command_for(sub).unwrap_or_default(),
+                description,
+                layer: config_merge::layer_name(p, &def.file),
+                file: def.file.to_string_lossy().into_owned(),
+                line: def.line,
+            });
+        }
+    }
+    Ok(out)
+}
+
+pub fn print_table(entries: &[Entry]) {
+    let width = entries.iter().map(|e| e.name().len()).max().unwrap_or(0);
+    let cmd_width = entries.iter().map(|e| e.command.len()).max().unwrap_or(0);
+    for e in entries {
+        let line = match &e.description {
+            Some(d) => format!(
+                "{:<width$}  {:<7}  {:<cmd_width$}  # {}",
+                e.name(),
+                e.layer,
+                e.command,
+                d,
+                width = width,
+                cmd_width = cmd_width
+            ),
+            None => format!(
+                "{:<width$}  {:<7}  {}",
+                e.name(),
+                e.layer,
+                e.command,
+                width = width
+            ),
+        };
+        println!("{}", line);
+    }
+}
+
+pub fn run(args: ListArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let mut list = entries(&p)?;
+    if let Some(layer) = args.layer.as_deref() {
+        if !["base", "group", "machine", "local"].contains(&layer) {
+            anyhow::bail!(
+                "unknown layer '{}' (expected base, group, machine or local)",
+                layer
+            );
+        }
+        list.retain(|e| e.layer == layer);
+    }
+    if let Some(prefix) = args.prefix.as_deref() {
+        list.retain(|e| e.alias.starts_with(prefix));
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&list)?);
+    } else if list.is_empty() {
+        println!("No aliases found");
+    } else {
+        print_table(&list);
+    }
+    Ok(())
+}
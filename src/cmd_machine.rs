@@ -1,13 +1,26 @@
+use crate::cli::{MachineAction, MachineArgs};
 use crate::config_merge;
 use crate::paths;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::fs;
+use std::path::PathBuf;
 
-pub fn run(name: Option<String>) -> Result<()> {
+pub fn run(args: MachineArgs) -> Result<()> {
     let p = paths::find_config_or_exit()?;
+    match args.action {
+        Some(MachineAction::List) => return list(&p),
+        Some(MachineAction::New { id, from }) => return new(&p, &id, from.as_deref()),
+        Some(MachineAction::Rename { from, to }) => return rename(&p, &from, &to),
+        Some(MachineAction::Rm { id }) => return remove(&p, &id),
+        Some(MachineAction::Show { id }) => return show(&p, id),
+        None => {}
+    }
+    if args.explain {
+        return explain(&p);
+    }
     let marker = p.repo_path.join(".ralf_machine");
 
-    if let Some(n) = name {
+    if let Some(n) = args.name {
         let n = n.trim();
         if let Some(dir) = marker.parent() {
             fs::create_dir_all(dir)?;
@@ -19,7 +32,7 @@ pub fn run(name: Option<String>) -> Result<()> {
         println!("Machine overlay: {}", mp.display());
         println!("Local overlay:   {}", lp.display());
         println!("Tip: run 'ralf edit machine' to edit the machine overlay.");
-        // Regenerate aliases so env exports (ralf_MACHINE, etc.) reflect the new machine
+        // Regenerate aliases so they reflect the new machine's overlay
         crate::cmd_save::run()?;
     } else {
         let mid = config_merge::resolve_machine_id(&p);
@@ -35,6 +48,238 @@ pub fn run(name: Option<String>) -> Result<()> {
             lp.display(),
             if lp.exists() { "exists" } else { "missing" }
         );
+        let privilege = config_merge::resolve_privilege(&p)?;
+        println!(
+            "Escalation:      {} (@root: {})",
+            privilege.tool.as_str(),
+            privilege.root.as_str()
+        );
+    }
+    Ok(())
+}
+
+/// Overlay path for `id`, after checking that the id is a usable file name.
+fn overlay(p: &paths::Paths, id: &str) -> Result<PathBuf> {
+    if !config_merge::valid_machine_id(id) {
+        bail!(
+            "invalid machine id '{}' (use letters, digits, '-', '_' and '.')",
+            id
+        );
+    }
+    Ok(config_merge::overlay_paths(p, id).0)
+}
+
+fn list(p: &paths::Paths) -> Result<()> {
+    let current = config_merge::resolve_machine_id(p);
+    let mut ids: Vec<String> = fs::read_dir(p.repo_path.join("machines"))
+        .map(|rd| {
+            rd.filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".conf").map(str::to_string)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    if ids.is_empty() {
+        println!(
+            "No machine overlays in {}",
+            p.repo_path.join("machines").display()
+        );
+        return Ok(());
+    }
+    let width = ids.iter().map(|i| i.len()).max().unwrap_or(0);
+    for id in &ids {
+        let file = config_merge::overlay_paths(p, id).0;
+        let count = crate::domain::parse_text(&fs::read_to_string(&file)?)?.len();
+        let commit = crate::gitwrap::last_commit(&p.repo_path, &file)
+            .unwrap_or_else(|| "not committed".to_string());
+        println!(
+            "{} {:<width$}  {:>3} aliases  {}",
+            if *id == current { "*" } else { " " },
+            id,
+            count,
+            commit,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+fn new(p: &paths::Paths, id: &str, from: Option<&str>) -> Result<()> {
+    let target = overlay(p, id)?;
+    if target.exists() {
+        bail!(
+            "machine '{}' already has an overlay: {}",
+            id,
+            target.display()
+        );
+    }
+    let text = match from {
+        Some(src) => {
+            let source = overlay(p, src)?;
+            if !source.exists() {
+                bail!("no overlay for machine '{}'", src);
+            }
+            fs::read_to_string(&source)?
+        }
+        None => crate::cmd_edit::MACHINE_TEMPLATE.replace("{id}", id),
+    };
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&target, text)?;
+    println!("Created {}", target.display());
+    if id == config_merge::resolve_machine_id(p) {
+        crate::cmd_save::run()?;
+    }
+    Ok(())
+}
+
+fn rename(p: &paths::Paths, from: &str, to: &str) -> Result<()> {
+    let source = overlay(p, from)?;
+    let target = overlay(p, to)?;
+    if !source.exists() {
+        bail!("no overlay for machine '{}'", from);
+    }
+    if target.exists() {
+        bail!(
+            "machine '{}' already has an overlay: {}",
+            to,
+            target.display()
+        );
+    }
+    let current = config_merge::resolve_machine_id(p);
+    fs::rename(&source, &target)?;
+    println!("Renamed {} to {}", source.display(), target.display());
+    if current == from {
+        let marker = p.repo_path.join(".ralf_machine");
+        if marker.exists() {
+            fs::write(&marker, to.as_bytes())?;
+            println!("Machine set to '{}'", to);
+        } else {
+            println!(
+                "Note: this machine is still identified as '{}'; run 'ralf machine {}' to follow the rename.",
+                from, to
+            );
+        }
+        crate::cmd_save::run()?;
+    }
+    Ok(())
+}
+
+fn remove(p: &paths::Paths, id: &str) -> Result<()> {
+    let target = overlay(p, id)?;
+    if !target.exists() {
+        bail!("no overlay for machine '{}'", id);
+    }
+    fs::remove_file(&target)?;
+    println!("Removed {}", target.display());
+    if id == config_merge::resolve_machine_id(p) {
+        crate::cmd_save::run()?;
+    }
+    Ok(())
+}
+
+fn show(p: &paths::Paths, id: Option<String>) -> Result<()> {
+    let id = id.unwrap_or_else(|| config_merge::resolve_machine_id(p));
+    let file = overlay(p, &id)?;
+    let layers = config_merge::machine_layers(p, &id);
+    println!(
+        "Machine: {} ({})",
+        id,
+        if file.exists() {
+            file.display().to_string()
+        } else {
+            "no overlay, base config only".to_string()
+        }
+    );
+    let privilege = config_merge::privilege_from(&layers)?;
+    println!(
+        "Escalation: {} (@root: {})",
+        privilege.tool.as_str(),
+        privilege.root.as_str()
+    );
+    println!();
+    let entries = crate::cmd_list::entries_from(p, &layers)?;
+    if entries.is_empty() {
+        println!("No aliases found");
+    } else {
+        crate::cmd_list::print_table(&entries);
+    }
+    Ok(())
+}
+
+fn explain(p: &paths::Paths) -> Result<()> {
+    use crate::machine_rules::{parse_rules, Facts};
+    let (id, source) = config_merge::explain_machine_id(p);
+    println!("Machine: {}", id);
+    println!("Source:  {}", source);
+
+    let mut facts = Facts::gather();
+    facts.machine = Some(id.clone());
+    let or_unknown = |v: Vec<String>| {
+        if v.is_empty() {
+            "unknown".to_string()
+        } else {
+            v.join(", ")
+        }
+    };
+    println!();
+    println!("Facts:");
+    println!("  hostname    {}", or_unknown(facts.hostnames.clone()));
+    println!(
+        "  machine-id  {}",
+        or_unknown(Vec::from_iter(facts.machine_id.clone()))
+    );
+    println!("  os          {}", or_unknown(facts.os.clone()));
+    println!(
+        "  user        {}",
+        or_unknown(Vec::from_iter(facts.user.clone()))
+    );
+
+    let rules_file = config_merge::rules_path(p);
+    println!();
+    let Ok(text) = fs::read_to_string(&rules_file) else {
+        println!("No rules file ({})", rules_file.display());
+        return Ok(());
+    };
+    println!("Rules ({}):", rules_file.display());
+    let (rules, errors) = parse_rules(&text);
+    let width = rules.iter().map(|r| r.id.len() + 1).max().unwrap_or(0);
+    // The rules only pick the id when nothing before them set it
+    let rule_decided = source.starts_with("rule at ");
+    let mut matched = false;
+    for rule in &rules {
+        let verdict = match rule.mismatch(&facts) {
+            None if rule.group => "in group".to_string(),
+            None if !matched && !rule_decided => {
+                matched = true;
+                format!("matches, but {} decided", source)
+            }
+            None if !matched => {
+                matched = true;
+                "matched".to_string()
+            }
+            None => "matches, but an earlier rule won".to_string(),
+            Some(why) => format!("no: {}", why),
+        };
+        println!(
+            "  {:>3}  {:<width$}  {:<30}  {}",
+            rule.line,
+            if rule.group {
+                format!("@{}", rule.id)
+            } else {
+                rule.id.clone()
+            },
+            rule.text,
+            verdict,
+            width = width
+        );
+    }
+    for (line, msg) in errors {
+        println!("  {:>3}  error: {}", line, msg);
     }
     Ok(())
 }
@@ -0,0 +1,109 @@
+use crate::cli::NewArgs;
+use crate::config_merge;
+use crate::gitwrap;
+use crate::paths;
+use crate::settings;
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::PathBuf;
+
+const STARTER: &str = "\
+# ralf aliases: one 'name: command' per line. Lines indented below an alias
+# are its subcommands, so 'g s' runs 'git status' and plain 'g' runs git.
+# Uncomment what you want, then run 'ralf save' (or 'ralf edit').
+#
+# g: git
+#   s: status
+#   # A comment right above an alias describes it in 'ralf list'
+#   l: log --oneline -20
+#   co: checkout
+#
+# k: kubectl
+#   gp: get pods
+#
+# Aliases that need root can say so; each machine's '@root:' decides how
+# upd: @root apt update
+#
+# Machine overlays in machines/<id>.conf override these per machine, and
+# ralf.local.conf (never committed) overrides everything on this machine.
+";
+
+pub fn run(args: NewArgs) -> Result<()> {
+    let dir = match args.dir.as_deref() {
+        Some(d) => std::env::current_dir()?.join(shellexpand::tilde(d).into_owned()),
+        None => paths::default_repo(),
+    };
+    for name in ["ralf.conf", "alf.conf"] {
+        if dir.join(name).exists() {
+            bail!(
+                "{} already has a {}; use 'ralf connect {}' to use it",
+                dir.display(),
+                name,
+                dir.display()
+            );
+        }
+    }
+
+    fs::create_dir_all(&dir)?;
+    // Stored as the repo setting, so without any `./` or `..` in it
+    let dir = dir.canonicalize()?;
+    let p = paths::Paths {
+        rc_file: paths::env_rc_file(),
+        aliases_file: paths::env_aliases_file(),
+        repo_path: dir.clone(),
+        config_file: dir.join("ralf.conf"),
+    };
+    fs::write(&p.config_file, STARTER)?;
+    let mut created: Vec<PathBuf> = vec![p.config_file.clone()];
+
+    let machine = config_merge::resolve_machine_id(&p);
+    if config_merge::valid_machine_id(&machine) {
+        let (overlay, _) = config_merge::overlay_paths(&p, &machine);
+        if let Some(parent) = overlay.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !overlay.exists() {
+            fs::write(
+                &overlay,
+                crate::cmd_edit::MACHINE_TEMPLATE.replace("{id}", &machine),
+            )?;
+            created.push(overlay);
+        }
+    }
+    let gitignore = dir.join(".gitignore");
+    let had_gitignore = gitignore.exists();
+    gitwrap::ensure_ralf_gitignore(&dir)?;
+    if !had_gitignore {
+        created.push(gitignore);
+    }
+
+    if !dir.join(".git").exists() {
+        gitwrap::init(&dir)?;
+    }
+    if let Some(url) = &args.remote {
+        gitwrap::add_remote(&dir, url)?;
+    }
+
+    println!("Created a config repo in {}", paths::friendly(&dir));
+    for f in &created {
+        println!("- {}", paths::friendly(f));
+    }
+    let stored = settings::store_repo(&dir, None)?;
+    println!("Storing location in {}", stored.display());
+    // A branch followed in the previous repo need not exist in this one
+    crate::cmd_branch::clear_tracked_branch()?;
+    crate::cmd_save::run()?;
+
+    println!();
+    println!("Next: add aliases with 'ralf edit' or 'ralf add <alias> <command>'");
+    if args.remote.is_some() {
+        println!("then 'ralf upload' pushes them; other machines 'ralf connect' to the same URL.");
+    } else {
+        println!(
+            "To share them, create an empty repo and run 'git -C {} remote add origin <url>',",
+            paths::friendly(&dir)
+        );
+        println!("then 'ralf upload'.");
+    }
+    Ok(())
+}
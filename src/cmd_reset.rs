@@ -8,6 +8,18 @@ pub fn run() -> Result<()> {
 
     let mut removed: Vec<String> = Vec::new();
 
+    // Repo location before rc and settings forget it
+    let mut repo: Option<PathBuf> = crate::settings::value("repo").map(PathBuf::from);
+    for key in ["repo", "sync"] {
+        if crate::settings::unset(key)? {
+            removed.push(format!(
+                "{} from {}",
+                key,
+                crate::paths::friendly(&crate::settings::path())
+            ));
+        }
+    }
+
     if rc.exists() {
         fs::remove_file(&rc).with_context(|| format!("failed removing {}", rc.display()))?;
         removed.push(crate::paths::friendly(&rc));
@@ -56,14 +68,7 @@ pub fn run() -> Result<()> {
         }
     }
 
-    // Try to find repo path from rc content (if it existed), else guess ./ralf-conf
-    let mut repo: Option<PathBuf> = None;
-    if let Ok(content) = fs::read_to_string(&rc) {
-        let s = content.trim();
-        if !s.is_empty() {
-            repo = Some(PathBuf::from(s));
-        }
-    }
+    // No repo recorded: guess ./ralf-conf
     if repo.is_none() {
         if let Ok(cwd) = std::env::current_dir() {
             let guess = cwd.join("ralf-conf");
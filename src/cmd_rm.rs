@@ -0,0 +1,25 @@
+use crate::cli::RmArgs;
+use crate::config_merge;
+use crate::domain::edit;
+use crate::paths;
+use anyhow::{bail, Result};
+use std::fs;
+
+pub fn run(args: RmArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let target = config_merge::layer_target(&p, args.layer.machine.as_deref(), args.layer.local)?;
+    let Ok(text) = fs::read_to_string(&target) else {
+        bail!("{} does not exist", paths::friendly(&target));
+    };
+
+    let (new_text, label) = match args.sub.as_deref() {
+        Some(s) => (
+            edit::remove_sub(&text, &args.alias, s)?,
+            format!("{} {}", args.alias, s),
+        ),
+        None => (edit::remove_alias(&text, &args.alias)?, args.alias.clone()),
+    };
+    fs::write(&target, new_text)?;
+    println!("Removed '{}' from {}", label, paths::friendly(&target));
+    crate::cmd_save::run()
+}
@@ -0,0 +1,81 @@
+use crate::domain::serialize_blocks;
+use crate::generator::{self, Shell};
+use anyhow::{Context, Result};
+use std::io::IsTerminal;
+use std::process::Command;
+
+/// Run an alias through the same function `ralf save` would generate for it,
+/// without sourcing anything. The shell replaces this process, so its exit
+/// status and signals are what the caller sees.
+pub fn run(words: Vec<String>) -> Result<()> {
+    let mut words = words.into_iter();
+    let alias = words.next().unwrap_or_default();
+    let args: Vec<String> = words.collect();
+    let p = crate::paths::find_config_or_exit()?;
+    let blocks = crate::config_merge::load_and_merge_model(&p)?;
+    let Some(block) = blocks.into_iter().find(|b| b.name == alias) else {
+        eprintln!("Error: No such alias: {}", alias);
+        std::process::exit(1);
+    };
+    let opts = generator::options_for(&p)?;
+    let text = serialize_blocks(std::slice::from_ref(&block));
+    let shell = generator::target_shell(&p.aliases_file);
+    // A terminal on stdin means a person can answer a password prompt, which the
+    // generated sudo wrapper only allows in interactive shells.
+    let interactive = std::io::stdin().is_terminal();
+
+    let mut cmd = match shell {
+        Shell::Fish => {
+            let script = format!(
+                "{}\n{} $argv\n",
+                generator::generate_config_fish_from_text(&text, &opts)?,
+                alias
+            );
+            let mut c = Command::new("fish");
+            c.arg("--no-config");
+            if interactive {
+                c.arg("-i");
+            }
+            c.arg("-c").arg(script);
+            c
+        }
+        Shell::Sh => {
+            let script = format!(
+                "{}\n{} \"$@\"\n",
+                generator::generate_config_sh_from_text(&text, &opts)?,
+                alias
+            );
+            let bin = if which::which("bash").is_ok() {
+                "bash"
+            } else {
+                "sh"
+            };
+            let mut c = Command::new(bin);
+            if bin == "bash" {
+                c.arg("--norc");
+            }
+            if interactive {
+                c.arg("-i");
+            }
+            c.arg("-c").arg(script).arg(&alias);
+            c
+        }
+    };
+    cmd.args(&args);
+    exec(cmd, shell)
+}
+
+#[cfg(unix)]
+fn exec(mut cmd: Command, shell: Shell) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+    let err = cmd.exec();
+    Err(err).with_context(|| format!("failed to start {} shell", shell.label()))
+}
+
+#[cfg(not(unix))]
+fn exec(mut cmd: Command, shell: Shell) -> Result<()> {
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to start {} shell", shell.label()))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -3,6 +3,7 @@ use anyhow::Result;
 pub fn run() -> Result<()> {
     let p = crate::paths::find_config_or_exit()?;
     let text = crate::config_merge::load_and_merge(&p)?;
+    let opts = crate::generator::options_for(&p)?;
     let cfg_dir = crate::paths::config_dir();
     std::fs::create_dir_all(&cfg_dir)?;
 
@@ -16,73 +17,52 @@ pub fn run() -> Result<()> {
             .replace('"', "\\\"")
             .replace('$', "\\$")
     };
-    let rc_q = esc(&p.rc_file.to_string_lossy());
     let machine = crate::config_merge::resolve_machine_id(&p);
-    let mid_q = esc(&machine);
-    let env_block_sh = |aliases_abs: &str| -> String {
-        format!(
-            r#"# ralf environment (auto-set)
-    export ralf_RC_FILE="${{ralf_RC_FILE:-{rc}}}"
-    export ALF_RC_FILE="${{ALF_RC_FILE:-{rc}}}"
-    export ralf_ALIASES_FILE="${{ralf_ALIASES_FILE:-{al}}}"
-    export ALF_ALIASES_FILE="${{ALF_ALIASES_FILE:-{al}}}"
-    export ralf_MACHINE="${{ralf_MACHINE:-{mid}}}"
-    if [ -z "$RALF_MACHINE" ] && [ -z "$ralf_MACHINE" ] && [ -z "$ALF_MACHINE" ] && [ -z "$alf_MACHINE" ]; then
-      export RALF_MACHINE="{mid}"; export ralf_MACHINE="{mid}"; export ALF_MACHINE="{mid}"; export alf_MACHINE="{mid}";
-    fi
-
-    "#,
-            rc = rc_q,
-            al = esc(aliases_abs),
-            mid = mid_q
-        )
+    // Stamped into every file written by this save; the shell exports it on
+    // load so `ralf status` can tell whether it runs the latest aliases
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let generation = now.as_millis().to_string();
+    let exports = rc_exports();
+    let env_block_sh = || -> String {
+        let mut block = String::from("# ralf environment (auto-set)\n");
+        for (var, value) in &exports {
+            block.push_str(&format!(
+                "    export {var}=\"${{{var}:-{value}}}\"\n",
+                value = esc(value)
+            ));
+        }
+        block.push_str(&format!(
+            "    export RALF_GENERATION=\"{}\"\n\n    ",
+            generation
+        ));
+        block
     };
-    let env_block_fish = |aliases_abs: &str| -> String {
-        format!(
-            r#"# ralf environment (auto-set)
-    if not set -q ralf_RC_FILE
-      set -gx ralf_RC_FILE "{rc}"
-    end
-    if not set -q ALF_RC_FILE
-      set -gx ALF_RC_FILE "{rc}"
-    end
-    if not set -q ralf_ALIASES_FILE
-      set -gx ralf_ALIASES_FILE "{al}"
-    end
-    if not set -q ALF_ALIASES_FILE
-      set -gx ALF_ALIASES_FILE "{al}"
-    end
-    if not set -q ralf_MACHINE
-      set -gx ralf_MACHINE "{mid}"
-    end
-    if not set -q RALF_MACHINE; and not set -q ralf_MACHINE; and not set -q ALF_MACHINE; and not set -q alf_MACHINE
-      set -gx RALF_MACHINE "{mid}"; set -gx ralf_MACHINE "{mid}"; set -gx ALF_MACHINE "{mid}"; set -gx alf_MACHINE "{mid}"
-    end
-
-    "#,
-            rc = rc_q,
-            al = esc(aliases_abs),
-            mid = mid_q
-        )
+    let env_block_fish = || -> String {
+        let mut block = String::from("# ralf environment (auto-set)\n");
+        for (var, value) in &exports {
+            block.push_str(&format!(
+                "    if not set -q {var}\n      set -gx {var} \"{value}\"\n    end\n",
+                value = esc(value)
+            ));
+        }
+        block.push_str(&format!(
+            "    set -gx RALF_GENERATION \"{}\"\n\n    ",
+            generation
+        ));
+        block
     };
 
     // Generate both unified variants
-    let mut sh_content = crate::generator::generate_config_sh_from_text(&text)?;
-    sh_content = format!(
-        "{}{}",
-        env_block_sh(&sh_target.to_string_lossy()),
-        sh_content
-    );
-    let mut fish_content = crate::generator::generate_config_fish_from_text(&text)?;
-    fish_content = format!(
-        "{}{}",
-        env_block_fish(&fish_target.to_string_lossy()),
-        fish_content
-    );
+    let mut sh_content = crate::generator::generate_config_sh_from_text(&text, &opts)?;
+    sh_content = format!("{}{}", env_block_sh(), sh_content);
+    let mut fish_content = crate::generator::generate_config_fish_from_text(&text, &opts)?;
+    fish_content = format!("{}{}", env_block_fish(), fish_content);
 
     // Save unified files
-    std::fs::write(&sh_target, sh_content)?;
-    std::fs::write(&fish_target, fish_content)?;
+    std::fs::write(&sh_target, &sh_content)?;
+    std::fs::write(&fish_target, &fish_content)?;
     if !in_tui {
         println!("Saved unified aliases to:");
         println!("- {}", crate::paths::friendly(&sh_target));
@@ -102,14 +82,12 @@ pub fn run() -> Result<()> {
             }
     };
     let compat_content = if is_target_fish {
-        let mut c = crate::generator::generate_config_fish_from_text(&text)?;
-        let al_q = esc(&p.aliases_file.to_string_lossy());
-        c = format!("{}{}", env_block_fish(&al_q), c);
+        let mut c = crate::generator::generate_config_fish_from_text(&text, &opts)?;
+        c = format!("{}{}", env_block_fish(), c);
         c
     } else {
-        let mut c = crate::generator::generate_config_sh_from_text(&text)?;
-        let al_q = esc(&p.aliases_file.to_string_lossy());
-        c = format!("{}{}", env_block_sh(&al_q), c);
+        let mut c = crate::generator::generate_config_sh_from_text(&text, &opts)?;
+        c = format!("{}{}", env_block_sh(), c);
         c
     };
 
@@ -119,7 +97,24 @@ pub fn run() -> Result<()> {
     if let Some(parent) = p.aliases_file.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    std::fs::write(&p.aliases_file, compat_content)?;
+    std::fs::write(&p.aliases_file, &compat_content)?;
+
+    // Keep this generation so 'ralf rollback' can return to it
+    let meta = crate::history::Meta {
+        generation: generation.clone(),
+        saved: now.as_secs(),
+        commit: p
+            .repo_path
+            .join(".git")
+            .exists()
+            .then(|| crate::gitwrap::head_commit(&p.repo_path))
+            .flatten(),
+        machine: machine.clone(),
+        compat_path: p.aliases_file.clone(),
+    };
+    if let Err(e) = crate::history::record(meta, &sh_content, &fish_content, &compat_content) {
+        eprintln!("warning: could not keep this generation: {}", e);
+    }
 
     // Install init stubs in rc files to source the unified files
     if let Some(home) = dirs::home_dir() {
@@ -185,6 +180,10 @@ pub fn run() -> Result<()> {
         }
     }
 
+    for s in crate::shadow::find_shadows(&crate::config_merge::load_and_merge_raw(&p)?) {
+        eprintln!("warning: {} (add @shadow-ok to silence)", s.describe());
+    }
+
     if in_tui {
         println!("Saved aliases.");
     } else {
@@ -193,3 +192,17 @@ pub fn run() -> Result<()> {
     }
     Ok(())
 }
+
+/// The rc file this save used, when the environment named one that records
+/// the repo. Nothing else is exported: a machine id or aliases path left in
+/// the environment would shadow config.toml and the machine rules in every
+/// shell loading the file, and could not be told apart from one the user set.
+fn rc_exports() -> Vec<(&'static str, String)> {
+    if !crate::paths::rc_env_active() {
+        return Vec::new();
+    }
+    ["RALF_RC_FILE", "ralf_RC_FILE", "ALF_RC_FILE"]
+        .into_iter()
+        .filter_map(|var| Some((var, std::env::var(var).ok()?)))
+        .collect()
+}
@@ -0,0 +1,58 @@
+use crate::cli::SearchArgs;
+use crate::cmd_list::{self, Entry};
+use crate::paths;
+use anyhow::Result;
+
+/// Fuzzy score of `query` in `hay`: substrings beat scattered subsequences,
+/// earlier and tighter matches beat later ones. None when it doesn't match.
+fn score(query: &str, hay: &str) -> Option<i64> {
+    let q = query.to_lowercase();
+    let h = hay.to_lowercase();
+    if q.is_empty() {
+        return None;
+    }
+    if let Some(pos) = h.find(&q) {
+        return Some(1000 - pos as i64);
+    }
+    let mut chars = h.char_indices();
+    let mut first = None;
+    let mut last = 0;
+    for qc in q.chars() {
+        let (i, _) = chars.find(|(_, c)| *c == qc)?;
+        first.get_or_insert(i);
+        last = i;
+    }
+    let span = (last - first.unwrap_or(0)) as i64;
+    Some(500 - span)
+}
+
+fn entry_score(query: &str, e: &Entry) -> Option<i64> {
+    let name = score(query, &e.name()).map(|s| s + 200);
+    let cmd = score(query, &e.command);
+    let desc = e.description.as_deref().and_then(|d| score(query, d));
+    [name, cmd, desc].into_iter().flatten().max()
+}
+
+pub fn run(args: SearchArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let mut hits: Vec<(i64, Entry)> = cmd_list::entries(&p)?
+        .into_iter()
+        .filter_map(|e| entry_score(&args.query, &e).map(|s| (s, e)))
+        .collect();
+    // Stable sort keeps config order among equal scores
+    hits.sort_by_key(|h| std::cmp::Reverse(h.0));
+    let list: Vec<Entry> = hits.into_iter().map(|(_, e)| e).collect();
+
+    // No matches exits 1 in either format, so scripts can tell
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&list)?);
+    } else if list.is_empty() {
+        println!("No aliases match '{}'", args.query);
+    } else {
+        cmd_list::print_table(&list);
+    }
+    if list.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
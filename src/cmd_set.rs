@@ -0,0 +1,6 @@
+use crate::cli::SetArgs;
+use anyhow::Result;
+
+pub fn run(args: SetArgs) -> Result<()> {
+    crate::cmd_add::write_alias(&args.words, &args.layer, true)
+}
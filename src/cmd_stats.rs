@@ -0,0 +1,171 @@
+use crate::cli::StatsArgs;
+use crate::config_merge;
+use crate::paths;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Local usage log; lives next to the generated files, never in the repo.
+pub fn log_file() -> PathBuf {
+    paths::config_dir().join("usage.log")
+}
+
+fn marker_file() -> PathBuf {
+    paths::config_dir().join("usage.enabled")
+}
+
+pub fn enabled() -> bool {
+    marker_file().exists()
+}
+
+struct Usage {
+    count: u64,
+    last: u64,
+}
+
+/// Parse `epoch<TAB>machine<TAB>alias<TAB>sub` lines for one machine.
+fn read_log(machine: &str) -> HashMap<String, Usage> {
+    let mut out: HashMap<String, Usage> = HashMap::new();
+    let text = fs::read_to_string(log_file()).unwrap_or_default();
+    for line in text.lines() {
+        let mut parts = line.split('\t');
+        let (Some(ts), Some(m), Some(alias)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        if m != machine {
+            continue;
+        }
+        let Ok(ts) = ts.parse::<u64>() else { continue };
+        let key = match parts.next().filter(|s| !s.is_empty()) {
+            Some(sub) => format!("{} {}", alias, sub),
+            None => alias.to_string(),
+        };
+        let u = out.entry(key).or_insert(Usage { count: 0, last: 0 });
+        u.count += 1;
+        u.last = u.last.max(ts);
+    }
+    out
+}
+
+pub fn run(args: StatsArgs) -> Result<()> {
+    if args.enable || args.disable {
+        let marker = marker_file();
+        if args.enable {
+            fs::create_dir_all(paths::config_dir())?;
+            fs::write(&marker, b"")?;
+            println!(
+                "Usage statistics enabled; logging to {}",
+                paths::friendly(&log_file())
+            );
+        } else {
+            if marker.exists() {
+                fs::remove_file(&marker)?;
+            }
+            println!("Usage statistics disabled");
+        }
+        return crate::cmd_save::run();
+    }
+
+    let p = paths::find_config_or_exit()?;
+    let machine = args
+        .machine
+        .unwrap_or_else(|| config_merge::resolve_machine_id(&p));
+    let usage = read_log(&machine);
+    if usage.is_empty() {
+        if enabled() {
+            println!("No usage recorded yet for machine '{}'", machine);
+        } else {
+            println!("Usage statistics are off.");
+            println!("Run 'ralf stats --enable' to start recording alias usage.");
+        }
+        return Ok(());
+    }
+
+    // Every invocable entry of that machine's merged config, in config order
+    if !config_merge::valid_machine_id(&machine) {
+        bail!("invalid machine id '{}'", machine);
+    }
+    let blocks = config_merge::merge_model(&config_merge::machine_layers(&p, &machine))?;
+    let mut known: Vec<String> = Vec::new();
+    for b in &blocks {
+        if !b.parent.starts_with('!') {
+            known.push(b.name.clone());
+        }
+        for (sname, _) in &b.subs {
+            known.push(format!("{} {}", b.name, sname));
+        }
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let days_ago = |ts: u64| now.saturating_sub(ts) / 86_400;
+    let total: u64 = usage.values().map(|u| u.count).sum();
+
+    println!("Machine: {} ({} calls recorded)", machine, total);
+    println!();
+
+    let mut ranked: Vec<(&String, &Usage)> = usage.iter().collect();
+    ranked.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+    println!("Most used:");
+    for (key, u) in ranked.iter().take(args.top) {
+        println!("  {:>6}  {}", u.count, key);
+    }
+
+    let never: Vec<&String> = known.iter().filter(|k| !usage.contains_key(*k)).collect();
+    println!();
+    println!("Never used:");
+    if never.is_empty() {
+        println!("  (none)");
+    }
+    for key in &never {
+        println!("  {}", key);
+    }
+
+    let stale: Vec<(&String, u64)> = known
+        .iter()
+        .filter_map(|k| usage.get(k).map(|u| (k, days_ago(u.last))))
+        .filter(|(_, d)| *d >= args.stale_days)
+        .collect();
+    println!();
+    println!("Stale (unused for {}+ days):", args.stale_days);
+    if stale.is_empty() {
+        println!("  (none)");
+    }
+    for (key, d) in &stale {
+        println!("  {}  (last used {} days ago)", key, d);
+    }
+
+    if args.suggest {
+        // An alias is a candidate when neither it nor any of its subs is in recent use
+        let recent = |k: &str| {
+            usage
+                .get(k)
+                .map(|u| days_ago(u.last) < args.stale_days)
+                .unwrap_or(false)
+        };
+        let candidates: Vec<&str> = blocks
+            .iter()
+            .filter(|b| {
+                !recent(&b.name)
+                    && !b
+                        .subs
+                        .iter()
+                        .any(|(s, _)| recent(&format!("{} {}", b.name, s)))
+            })
+            .map(|b| b.name.as_str())
+            .collect();
+        println!();
+        println!("Removal candidates:");
+        if candidates.is_empty() {
+            println!("  (none)");
+        }
+        for name in candidates {
+            println!("  {}", name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use crate::cli::StatusArgs;
+use crate::config_merge;
+use crate::paths;
+use anyhow::Result;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub fn run(args: StatusArgs) -> Result<()> {
+    let p = paths::find_config_or_exit()?;
+    let repo = &p.repo_path;
+    let machine = config_merge::resolve_machine_id(&p);
+    let is_repo = repo.join(".git").exists();
+    if args.fetch && is_repo {
+        crate::gitwrap::fetch(repo)?;
+    }
+
+    let branch = is_repo
+        .then(|| crate::gitwrap::current_branch(repo))
+        .flatten();
+    let upstream = is_repo.then(|| crate::gitwrap::upstream(repo)).flatten();
+    let changes: Vec<(String, String, String)> = if is_repo {
+        crate::gitwrap::status(repo, &[])?
+            .into_iter()
+            .map(|(code, file)| (layer_of(&p, &file, &machine), file, code))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // The newest layer (or the rules that pick the machine) dates the config
+    let mut inputs = config_merge::layer_files(&p, &machine);
+    inputs.push(config_merge::rules_path(&p));
+    let config_time = inputs.iter().filter_map(|f| modified(f)).max();
+    let cfg_dir = paths::config_dir();
+    let mut generated = vec![cfg_dir.join("aliases.sh"), cfg_dir.join("aliases.fish")];
+    if !generated.contains(&p.aliases_file) {
+        generated.push(p.aliases_file.clone());
+    }
+    let generated: Vec<(PathBuf, &str)> = generated
+        .into_iter()
+        .map(|f| {
+            let state = match (modified(&f), config_time) {
+                (None, _) => "missing",
+                (Some(g), Some(c)) if g < c => "stale",
+                _ => "current",
+            };
+            (f, state)
+        })
+        .collect();
+
+    let latest = std::fs::read_to_string(&p.aliases_file)
+        .ok()
+        .and_then(|t| crate::generator::generation(&t));
+    let loaded = std::env::var("RALF_GENERATION")
+        .ok()
+        .filter(|g| !g.is_empty());
+    let shell_state = match (&loaded, &latest) {
+        (None, _) => "not loaded",
+        (Some(l), Some(g)) if l == g => "current",
+        _ => "stale",
+    };
+
+    if args.json {
+        let out = json!({
+            "repo": repo,
+            "machine": machine,
+            "branch": branch,
+            "upstream": upstream.as_ref().map(|(name, ahead, behind)| json!({
+                "name": name,
+                "ahead": ahead,
+                "behind": behind,
+            })),
+            "changes": changes.iter().map(|(layer, file, code)| json!({
+                "layer": layer,
+                "file": file,
+                "status": code.trim(),
+            })).collect::<Vec<_>>(),
+            "generated": generated.iter().map(|(f, state)| json!({
+                "file": f,
+                "state": state,
+            })).collect::<Vec<_>>(),
+            "shell": {
+                "loaded": loaded,
+                "latest": latest,
+                "state": shell_state,
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    println!("Repository: {}", paths::friendly(repo));
+    if !is_repo {
+        println!("  not a git repository");
+    } else {
+        let on = branch.as_deref().unwrap_or("(detached HEAD)");
+        match &upstream {
+            None => println!("  on {}, no upstream", on),
+            Some((name, 0, 0)) => println!("  on {}, up to date with {}", on, name),
+            Some((name, ahead, behind)) => println!(
+                "  on {}, {} ahead and {} behind {} (run 'ralf {}')",
+                on,
+                ahead,
+                behind,
+                name,
+                if *behind > 0 { "download" } else { "upload" }
+            ),
+        }
+    }
+    println!();
+
+    if changes.is_empty() {
+        println!("Uncommitted changes: none");
+    } else {
+        println!("Uncommitted changes:");
+        let mut layers: Vec<&String> = Vec::new();
+        for (l, _, _) in &changes {
+            if !layers.contains(&l) {
+                layers.push(l);
+            }
+        }
+        for layer in layers {
+            println!("  {}", layer);
+            for (_, file, code) in changes.iter().filter(|(l, _, _)| l == layer) {
+                println!("    {} {}", code, file);
+            }
+        }
+    }
+    println!();
+
+    println!("Generated files:");
+    for (f, state) in &generated {
+        let note = match *state {
+            "missing" => "missing (run 'ralf save')",
+            "stale" => "older than the config (run 'ralf save')",
+            _ => "up to date",
+        };
+        println!("  {}: {}", paths::friendly(f), note);
+    }
+    println!();
+
+    let source = format!("source {}", paths::friendly(&p.aliases_file));
+    match shell_state {
+        "not loaded" => println!("Current shell: ralf aliases not loaded (run '{}')", source),
+        "current" => println!("Current shell: latest aliases loaded"),
+        _ => println!(
+            "Current shell: running an older generation (run '{}')",
+            source
+        ),
+    }
+    Ok(())
+}
+
+/// Layer a repo-relative path belongs to, for grouping.
+fn layer_of(p: &paths::Paths, file: &str, machine: &str) -> String {
+    let base = p.config_file.strip_prefix(&p.repo_path).ok();
+    if base == Some(Path::new(file)) {
+        return "base".to_string();
+    }
+    if file == "machines/rules" {
+        return "machine rules".to_string();
+    }
+    if let Some(group) = file
+        .strip_prefix("machines/groups/")
+        .and_then(|f| f.strip_suffix(".conf"))
+    {
+        return format!("group {}", group);
+    }
+    if let Some(id) = file
+        .strip_prefix("machines/")
+        .and_then(|f| f.strip_suffix(".conf"))
+    {
+        return if id == machine {
+            format!("machine {} (this machine)", id)
+        } else {
+            format!("machine {}", id)
+        };
+    }
+    if file == "ralf.local.conf" || file == "alf.local.conf" {
+        return "local".to_string();
+    }
+    "other".to_string()
+}
+
+fn modified(f: &Path) -> Option<SystemTime> {
+    std::fs::metadata(f).and_then(|m| m.modified()).ok()
+}
@@ -1,6 +1,203 @@
-use anyhow::Result;
+use crate::cli::UpgradeArgs;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-pub fn run() -> Result<()> {
-    eprintln!("Upgrade is not yet implemented in the Rust port.");
+/// Release manifest, e.g.
+/// `{"version": "0.7.0", "assets": {"x86_64-linux": {"url": "...", "sha256": "..."}}}`.
+/// Asset URLs may be relative to the manifest.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: String,
+    assets: BTreeMap<String, Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    url: String,
+    sha256: String,
+}
+
+pub fn run(args: UpgradeArgs) -> Result<()> {
+    let Some(source) = args
+        .manifest
+        .clone()
+        .or_else(|| crate::settings::value("upgrade_url"))
+    else {
+        bail!(
+            "no release manifest configured; pass --manifest <url|file> or run 'ralf config set upgrade_url <url>'"
+        );
+    };
+    let manifest: Manifest = serde_json::from_slice(&fetch(&source)?)
+        .with_context(|| format!("invalid release manifest {}", source))?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    if prerelease(&manifest.version) {
+        println!(
+            "ralf {} is a pre-release; staying on {}",
+            manifest.version.trim(),
+            current
+        );
+        return Ok(());
+    }
+    if !newer(&manifest.version, current) {
+        println!("ralf {} is up to date", current);
+        return Ok(());
+    }
+    let target = target();
+    let asset = manifest.assets.get(&target);
+    if args.check {
+        println!(
+            "ralf {} is available (you have {}){}",
+            manifest.version,
+            current,
+            if asset.is_none() {
+                format!(", but not for {}", target)
+            } else {
+                String::new()
+            }
+        );
+        return Ok(());
+    }
+    let Some(asset) = asset else {
+        bail!(
+            "release {} has no build for {} (has: {})",
+            manifest.version,
+            target,
+            manifest
+                .assets
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+
+    let url = resolve(&source, &asset.url);
+    println!("Downloading ralf {} from {}", manifest.version, url);
+    let bytes = fetch(&url)?;
+    let digest = hex(&Sha256::digest(&bytes));
+    if !digest.eq_ignore_ascii_case(asset.sha256.trim()) {
+        bail!(
+            "checksum mismatch for {}: expected {}, got {}; nothing was changed",
+            url,
+            asset.sha256.trim(),
+            digest
+        );
+    }
+
+    let exe = std::env::current_exe()?.canonicalize()?;
+    let backup = install(&exe, &bytes)?;
+    println!("Upgraded ralf {} -> {}", current, manifest.version);
+    println!("- {}", exe.display());
+    println!("Previous version kept as {}", backup.display());
     Ok(())
 }
+
+/// Put `bytes` in place of `exe` with a rename in the same directory, so the
+/// binary is never half written, after copying the old one to `<exe>.bak`.
+fn install(exe: &Path, bytes: &[u8]) -> Result<PathBuf> {
+    let name = exe
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "ralf".to_string());
+    let tmp = exe.with_file_name(format!(".{}.upgrade-{}", name, std::process::id()));
+    let backup = exe.with_file_name(format!("{}.bak", name));
+    let staged = (|| -> Result<()> {
+        fs::write(&tmp, bytes)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o755))?;
+        }
+        fs::copy(exe, &backup)?;
+        fs::rename(&tmp, exe)?;
+        Ok(())
+    })();
+    if let Err(e) = staged {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("cannot replace {}", exe.display()));
+    }
+    Ok(backup)
+}
+
+/// Manifest key for this platform, such as `x86_64-linux` or `aarch64-macos`.
+fn target() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// Whether `version` has a pre-release part, as in `0.7.0-rc1`; build
+/// metadata after `+` does not count.
+fn prerelease(version: &str) -> bool {
+    version.split('+').next().unwrap_or("").contains('-')
+}
+
+/// Whether `candidate` is a later `major.minor.patch` than `current`.
+fn newer(candidate: &str, current: &str) -> bool {
+    fn parts(v: &str) -> Vec<u64> {
+        v.trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or("")
+            .split('.')
+            .map(|n| n.parse().unwrap_or(0))
+            .collect()
+    }
+    let (a, b) = (parts(candidate), parts(current));
+    let len = a.len().max(b.len());
+    let pad = |v: &[u64]| {
+        (0..len)
+            .map(|i| v.get(i).copied().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    pad(&a) > pad(&b)
+}
+
+/// `url` as given when absolute, else next to the manifest.
+fn resolve(manifest: &str, url: &str) -> String {
+    if url.contains("://") || url.starts_with('/') {
+        return url.to_string();
+    }
+    match manifest.rfind('/') {
+        Some(i) => format!("{}/{}", &manifest[..i], url),
+        None => url.to_string(),
+    }
+}
+
+/// Read a local path or `file://` URL, or download with curl (else wget).
+fn fetch(source: &str) -> Result<Vec<u8>> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        let path = shellexpand::tilde(path).into_owned();
+        return fs::read(&path).with_context(|| format!("cannot read {}", path));
+    }
+    let attempts: [(&str, &[&str]); 2] =
+        [("curl", &["-fsSL", source]), ("wget", &["-qO-", source])];
+    for (tool, args) in attempts {
+        if which::which(tool).is_err() {
+            continue;
+        }
+        let out = Command::new(tool)
+            .args(args)
+            .output()
+            .with_context(|| format!("failed to spawn {}", tool))?;
+        if !out.status.success() {
+            bail!(
+                "download of {} failed: {}",
+                source,
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        return Ok(out.stdout);
+    }
+    bail!("downloading {} needs curl or wget in PATH", source)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,16 +1,163 @@
+use crate::cli::UploadArgs;
+use crate::domain::{diff_blocks, parse_text, ChangeKind};
 use crate::paths;
 use anyhow::Result;
+use std::io::IsTerminal;
+use std::path::Path;
 
-pub fn run() -> Result<()> {
-    let rc = paths::env_rc_file();
-    if !rc.exists() {
-        println!("Cannot find {}", rc.display());
-        println!("Please connect ralf to a repository first");
-        std::process::exit(1);
-    }
-
+pub fn run(args: UploadArgs) -> Result<()> {
+    crate::settings::require_repo();
     let p = paths::find_config_or_exit()?;
+    crate::sync::backend()?.upload(&p, &args)
+}
+
+/// Commit the managed files (or everything with `--all`) and push.
+pub fn git_upload(p: &paths::Paths, args: &UploadArgs) -> Result<()> {
     println!("Pushing {} to repository", p.repo_path.display());
-    crate::gitwrap::commit_all_and_push(&p.repo_path)?;
+    crate::cmd_branch::ensure_tracked(p)?;
+    crate::gitwrap::ensure_ralf_gitignore(&p.repo_path)?;
+
+    let pathspecs = if args.all {
+        Vec::new()
+    } else {
+        managed_paths(p)
+    };
+    let changes = crate::gitwrap::status(&p.repo_path, &pathspecs)?;
+    if !args.all {
+        let all = crate::gitwrap::status(&p.repo_path, &[])?;
+        let skipped: Vec<&String> = all
+            .iter()
+            .filter(|(_, f)| !changes.iter().any(|(_, c)| c == f))
+            .map(|(_, f)| f)
+            .collect();
+        if !skipped.is_empty() {
+            println!("Not staged (not managed by ralf; use --all to include):");
+            for f in skipped {
+                println!("  {}", f);
+            }
+        }
+    }
+
+    let message = if changes.is_empty() {
+        println!("No changes to commit");
+        None
+    } else {
+        let message = args
+            .message
+            .clone()
+            .unwrap_or_else(|| default_message(&p.repo_path, &p.config_file, &changes));
+        println!("Changes to commit:");
+        for (code, file) in &changes {
+            println!("  {} {}", code, file);
+        }
+        println!("Message: {}", message);
+        Some(message)
+    };
+
+    if !changes.is_empty()
+        && !args.yes
+        && std::io::stdin().is_terminal()
+        && !crate::tui::confirm("Commit and push these changes? [yN]")?
+    {
+        println!("Aborting");
+        return Ok(());
+    }
+    crate::gitwrap::commit_and_push(&p.repo_path, &pathspecs, message.as_deref())?;
     Ok(())
 }
+
+/// Files `upload` stages by default: the base config, the machines directory
+/// (overlays and rules) and the `.gitignore` ralf maintains. Only those on
+/// disk or tracked are listed, as git rejects a pathspec matching nothing.
+pub fn managed_paths(p: &paths::Paths) -> Vec<String> {
+    let base = p
+        .config_file
+        .strip_prefix(&p.repo_path)
+        .unwrap_or(&p.config_file)
+        .to_string_lossy()
+        .into_owned();
+    [base, "machines".to_string(), ".gitignore".to_string()]
+        .into_iter()
+        .filter(|f| p.repo_path.join(f).exists() || crate::gitwrap::tracked(&p.repo_path, f))
+        .collect()
+}
+
+/// Describe the changes alias by alias, e.g. "add g.l, change d.up on machine web1".
+fn default_message(repo: &Path, config_file: &Path, changes: &[(String, String)]) -> String {
+    let base = config_file
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Base config first, then overlays, then anything else
+    let mut ordered: Vec<&(String, String)> = changes.iter().collect();
+    ordered.sort_by_key(|(_, f)| (*f != base, !f.starts_with("machines/")));
+    let mut parts = Vec::new();
+    for (code, file) in ordered {
+        let overlay = match file.strip_prefix("machines/groups/") {
+            Some(f) => f.strip_suffix(".conf").map(|g| ("group", g)),
+            None => file
+                .strip_prefix("machines/")
+                .and_then(|f| f.strip_suffix(".conf"))
+                .map(|id| ("machine", id)),
+        };
+        let (what, suffix) = match overlay {
+            Some((kind, id)) => (format!("{} {}", kind, id), format!(" on {} {}", kind, id)),
+            None if *file == base => (file.clone(), String::new()),
+            None => {
+                parts.push(format!("update {}", file));
+                continue;
+            }
+        };
+        let old = crate::gitwrap::show_file(repo, "HEAD", Path::new(file))
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let new = std::fs::read_to_string(repo.join(file)).unwrap_or_default();
+        let diff = diff_blocks(
+            &parse_text(&old).unwrap_or_default(),
+            &parse_text(&new).unwrap_or_default(),
+        );
+        let items: Vec<String> = diff
+            .iter()
+            .map(|c| {
+                let verb = match c.kind {
+                    ChangeKind::Added => "add",
+                    ChangeKind::Removed => "remove",
+                    ChangeKind::Changed => "change",
+                };
+                let name = match &c.sub {
+                    Some(s) => format!("{}.{}", c.alias, s),
+                    None => c.alias.clone(),
+                };
+                format!("{} {}", verb, name)
+            })
+            .collect();
+        let summary = if items.is_empty() {
+            match code.trim() {
+                "D" => format!("remove {}", what),
+                "??" | "A" => format!("add {}", what),
+                _ => format!("update {}", what),
+            }
+        } else if items.len() > 6 {
+            let count = |k: ChangeKind| diff.iter().filter(|c| c.kind == k).count();
+            let counts: Vec<String> = [
+                ("add", ChangeKind::Added),
+                ("change", ChangeKind::Changed),
+                ("remove", ChangeKind::Removed),
+            ]
+            .iter()
+            .filter(|(_, k)| count(*k) > 0)
+            .map(|(v, k)| format!("{} {}", v, count(*k)))
+            .collect();
+            format!("{} aliases{}", counts.join(", "), suffix)
+        } else {
+            format!("{}{}", items.join(", "), suffix)
+        };
+        parts.push(summary);
+    }
+    if parts.is_empty() {
+        "update aliases".to_string()
+    } else {
+        parts.join("; ")
+    }
+}
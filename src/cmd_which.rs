@@ -1,21 +1,28 @@
+use crate::domain::{AliasBlock, Definition};
+use crate::generator;
 use anyhow::Result;
 
-pub fn run(code: String, subcode: Option<String>) -> Result<()> {
+pub fn run(code: String, subcode: Option<String>, trace: bool) -> Result<()> {
     let p = crate::paths::find_config_or_exit()?;
-    let blocks = crate::config_merge::load_and_merge_model(&p)?;
-
-    if let Some(b) = blocks.iter().find(|b| b.name == code) {
-        if let Some(sc) = subcode.as_ref() {
-            if let Some((_, scmd)) = b.subs.iter().find(|(n, _)| n == sc) {
-                if scmd.starts_with('!') {
-                    println!("{}", &scmd[1..]);
-                } else {
-                    println!("{} {}", b.parent, scmd);
-                }
-                return Ok(());
+    if trace {
+        let (blocks, defs) = crate::config_merge::load_and_merge_traced(&p)?;
+        if let Some(b) = blocks.iter().find(|b| b.name == code) {
+            if subcode
+                .as_ref()
+                .is_none_or(|sc| b.subs.iter().any(|(n, _)| n == sc))
+            {
+                return print_trace(&p, b, subcode.as_deref(), &defs);
             }
-        } else {
-            println!("{}", b.parent);
+        }
+    } else {
+        let blocks = crate::config_merge::load_and_merge_model(&p)?;
+
+        if let Some(cmd) = blocks
+            .iter()
+            .find(|b| b.name == code)
+            .and_then(|b| b.command_for(subcode.as_deref()))
+        {
+            println!("{}", cmd);
             return Ok(());
         }
     }
@@ -27,3 +34,47 @@ pub fn run(code: String, subcode: Option<String>) -> Result<()> {
     );
     std::process::exit(1);
 }
+
+fn print_trace(
+    p: &crate::paths::Paths,
+    block: &AliasBlock,
+    sub: Option<&str>,
+    defs: &[Definition],
+) -> Result<()> {
+    let location = |d: &Definition| {
+        let file = d.file.strip_prefix(&p.repo_path).unwrap_or(&d.file);
+        format!("{}:{}", file.display(), d.line)
+    };
+    let print_layers = |label: String, sub: Option<&str>| {
+        println!("{}", label);
+        let hits: Vec<&Definition> = defs
+            .iter()
+            .filter(|d| d.alias == block.name && d.sub.as_deref() == sub)
+            .collect();
+        let width = hits.iter().map(|d| location(d).len()).max().unwrap_or(0);
+        for (i, d) in hits.iter().enumerate() {
+            let how = if i == 0 { "defined" } else { "overridden" };
+            println!(
+                "  {:<10} {:<width$}  {}",
+                how,
+                location(d),
+                d.command,
+                width = width
+            );
+        }
+    };
+
+    print_layers(block.name.clone(), None);
+    if let Some(sc) = sub {
+        print_layers(format!("{} {}", block.name, sc), Some(sc));
+    }
+
+    let shell = generator::target_shell(&p.aliases_file);
+    println!();
+    println!("Repo:   {}", p.repo_path.display());
+    match generator::expansion(block, sub, shell)? {
+        Some(line) => println!("{}: {}", shell.label(), line),
+        None => println!("{}: (not generated for this shell)", shell.label()),
+    }
+    Ok(())
+}
@@ -1,18 +1,26 @@
-use crate::domain::{merge_blocks, parse_text, serialize_blocks, AliasBlock};
+use crate::domain::{
+    merge_blocks, parse_definitions, parse_directives, parse_text, resolve_annotations,
+    serialize_blocks, AliasBlock, Definition, Escalation, Privilege, RootPolicy,
+};
 use crate::paths;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn resolve_machine_id(p: &paths::Paths) -> String {
+    explain_machine_id(p).0
+}
+
+/// Current machine id and where it came from, for `ralf machine --explain`.
+pub fn explain_machine_id(p: &paths::Paths) -> (String, String) {
     // 1) Repo marker wins
     let marker = p.repo_path.join(".ralf_machine");
     if marker.exists() {
         if let Ok(s) = std::fs::read_to_string(&marker) {
             let t = s.trim().to_string();
             if !t.is_empty() {
-                return t;
+                return (t, format!("marker file {}", marker.display()));
             }
         }
     }
@@ -23,37 +31,101 @@ pub fn resolve_machine_id(p: &paths::Paths) -> String {
             let t = s.trim().to_string();
             if !t.is_empty() {
                 let _ = std::fs::rename(&legacy, p.repo_path.join(".ralf_machine"));
-                return t;
+                return (t, format!("legacy marker file {}", legacy.display()));
             }
         }
     }
 
-    // 2) Environment variables (new + legacy, common casings)
-    if let Ok(s) = std::env::var("RALF_MACHINE")
-        .or_else(|_| std::env::var("ALF_MACHINE"))
-        .or_else(|_| std::env::var("ralf_MACHINE"))
-        .or_else(|_| std::env::var("alf_MACHINE"))
-    {
-        let t = s.trim().to_string();
-        if !t.is_empty() {
-            return t;
+    // 2) Environment variables (new + legacy, common casings), then the setting
+    if let Some((id, source)) = crate::settings::lookup("machine") {
+        return (id, source.describe());
+    }
+
+    // 3) First matching rule in machines/rules
+    let rules_file = rules_path(p);
+    if let Ok(text) = fs::read_to_string(&rules_file) {
+        let facts = crate::machine_rules::Facts::gather();
+        let (rules, _) = crate::machine_rules::parse_rules(&text);
+        if let Some(rule) = rules
+            .iter()
+            .find(|r| !r.group && r.mismatch(&facts).is_none())
+        {
+            return (
+                rule.id.clone(),
+                format!(
+                    "rule at {}:{} ({})",
+                    rules_file.display(),
+                    rule.line,
+                    rule.text
+                ),
+            );
         }
     }
 
-    // 3) Host fallback
+    // 4) Host fallback
     if let Ok(h) = std::env::var("HOSTNAME") {
         let t = h.trim().to_string();
         if !t.is_empty() {
-            return t;
+            return (t, "$HOSTNAME".to_string());
         }
     }
     if let Ok(out) = Command::new("hostname").arg("-s").output() {
         let t = String::from_utf8_lossy(&out.stdout).trim().to_string();
         if !t.is_empty() {
-            return t;
+            return (t, "hostname -s".to_string());
+        }
+    }
+    (
+        "unknown".to_string(),
+        "no marker, variable, rule or hostname".to_string(),
+    )
+}
+
+/// Rules mapping host facts to machine ids, see `machine_rules`.
+pub fn rules_path(p: &paths::Paths) -> PathBuf {
+    p.repo_path.join("machines").join("rules")
+}
+
+/// Groups `machine` is in: the `@<group>:` rules that hold, in file order.
+/// Only the machine id is known about other machines, so their host and
+/// environment conditions do not hold.
+pub fn groups(p: &paths::Paths, machine: &str) -> Vec<String> {
+    let Ok(text) = fs::read_to_string(rules_path(p)) else {
+        return Vec::new();
+    };
+    let (rules, _) = crate::machine_rules::parse_rules(&text);
+    if !rules.iter().any(|r| r.group) {
+        return Vec::new();
+    }
+    let mut facts = if machine == resolve_machine_id(p) {
+        crate::machine_rules::Facts::gather()
+    } else {
+        crate::machine_rules::Facts::default()
+    };
+    facts.machine = Some(machine.to_string());
+    let mut out: Vec<String> = Vec::new();
+    for rule in rules.iter().filter(|r| r.group) {
+        if rule.mismatch(&facts).is_none() && !out.contains(&rule.id) {
+            out.push(rule.id.clone());
         }
     }
-    "unknown".to_string()
+    out
+}
+
+pub fn group_path(p: &paths::Paths, group: &str) -> PathBuf {
+    p.repo_path
+        .join("machines")
+        .join("groups")
+        .join(format!("{}.conf", group))
+}
+
+/// Machine ids name overlay files, so they must not contain path separators.
+pub fn valid_machine_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
 }
 
 pub fn overlay_paths(p: &paths::Paths, machine: &str) -> (PathBuf, PathBuf) {
@@ -66,57 +138,212 @@ pub fn overlay_paths(p: &paths::Paths, machine: &str) -> (PathBuf, PathBuf) {
 }
 
 pub fn load_and_merge(p: &paths::Paths) -> Result<String> {
-    let base_text = fs::read_to_string(&p.config_file)?;
-    let mut blocks = parse_text(&base_text)?;
-    let machine = resolve_machine_id(p);
-    let (machine_path, local_path) = overlay_paths(p, &machine);
+    Ok(serialize_blocks(&load_and_merge_model(p)?))
+}
+
+/// Merged model with annotations resolved for the current machine.
+pub fn load_and_merge_model(p: &paths::Paths) -> Result<Vec<AliasBlock>> {
+    let layers = layer_files(p, &resolve_machine_id(p));
+    merge_model(&layers)
+}
+
+/// Merged model as written in the layers, annotations still in place.
+pub fn load_and_merge_raw(p: &paths::Paths) -> Result<Vec<AliasBlock>> {
+    merge_files(&layer_files(p, &resolve_machine_id(p)))
+}
+
+/// Merged model plus every definition in layer order, so callers can tell
+/// which layer set or overrode each parent and sub.
+pub fn load_and_merge_traced(p: &paths::Paths) -> Result<(Vec<AliasBlock>, Vec<Definition>)> {
+    merge_traced(&layer_files(p, &resolve_machine_id(p)))
+}
+
+/// Layers that apply to `machine`. The local overlay only belongs to the
+/// machine this checkout is on, so other ids get base plus their overlay.
+pub fn machine_layers(p: &paths::Paths, machine: &str) -> Vec<PathBuf> {
+    let mut layers = layer_files(p, machine);
+    if machine != resolve_machine_id(p) {
+        layers.retain(|l| layer_name(p, l) != "local");
+    }
+    layers
+}
+
+/// Merge the given layer files in order.
+pub fn merge_files(layers: &[PathBuf]) -> Result<Vec<AliasBlock>> {
+    let mut blocks = Vec::new();
+    for layer in layers {
+        let text = fs::read_to_string(layer)?;
+        blocks = merge_blocks(blocks, parse_text(&text)?);
+    }
+    Ok(blocks)
+}
+
+/// `merge_files` with annotations resolved by the layers' own `@root` policy.
+pub fn merge_model(layers: &[PathBuf]) -> Result<Vec<AliasBlock>> {
+    merge_sources(&read_layers(layers)?)
+}
+
+/// `merge_model` over layer texts that may not be on disk, e.g. read from
+/// another revision. The paths only label errors.
+pub fn merge_sources(sources: &[(PathBuf, String)]) -> Result<Vec<AliasBlock>> {
+    let privilege = privilege_from_sources(sources)?;
+    let mut blocks = Vec::new();
+    for (_, text) in sources {
+        blocks = merge_blocks(blocks, parse_text(text)?);
+    }
+    Ok(resolve_annotations(blocks, privilege.root))
+}
+
+fn read_layers(layers: &[PathBuf]) -> Result<Vec<(PathBuf, String)>> {
+    layers
+        .iter()
+        .map(|l| Ok((l.clone(), fs::read_to_string(l)?)))
+        .collect()
+}
+
+/// `merge_model` plus the definitions of every layer, in layer order.
+pub fn merge_traced(layers: &[PathBuf]) -> Result<(Vec<AliasBlock>, Vec<Definition>)> {
+    let mut defs = Vec::new();
+    for layer in layers {
+        let text = fs::read_to_string(layer)?;
+        defs.extend(parse_definitions(&text, layer)?);
+    }
+    Ok((merge_model(layers)?, defs))
+}
 
+/// Existing config layers in merge order: base, group overlays, machine
+/// overlay, local overlay.
+pub fn layer_files(p: &paths::Paths, machine: &str) -> Vec<PathBuf> {
+    let (machine_path, local_path) = overlay_paths(p, machine);
+    let mut out = vec![p.config_file.clone()];
+    for group in groups(p, machine) {
+        let file = group_path(p, &group);
+        if file.exists() {
+            out.push(file);
+        }
+    }
     if machine_path.exists() {
-        let overlay = fs::read_to_string(&machine_path)?;
-        let ob = parse_text(&overlay)?;
-        blocks = merge_blocks(blocks, ob);
+        out.push(machine_path);
     }
     if local_path.exists() {
-        let overlay = fs::read_to_string(&local_path)?;
-        let ob = parse_text(&overlay)?;
-        blocks = merge_blocks(blocks, ob);
-    }
-    // Legacy local overlay fallback: alf.local.conf
-    if !local_path.exists() {
+        out.push(local_path);
+    } else {
         let legacy_local = p.repo_path.join("alf.local.conf");
         if legacy_local.exists() {
-            let overlay = fs::read_to_string(&legacy_local)?;
-            let ob = parse_text(&overlay)?;
-            blocks = merge_blocks(blocks, ob);
+            out.push(legacy_local);
         }
     }
-    Ok(serialize_blocks(&blocks))
+    out
 }
 
-pub fn load_and_merge_model(p: &paths::Paths) -> Result<Vec<AliasBlock>> {
-    let base_text = std::fs::read_to_string(&p.config_file)?;
-    let mut blocks = parse_text(&base_text)?;
+/// File a command-line edit goes to: the base config, a machine overlay or the local overlay.
+/// Machine ids that are not plain file names are refused.
+pub fn layer_target(p: &paths::Paths, machine: Option<&str>, local: bool) -> Result<PathBuf> {
+    Ok(if local {
+        p.repo_path.join("ralf.local.conf")
+    } else if let Some(m) = machine {
+        if !valid_machine_id(m) {
+            bail!("invalid machine id '{}'", m);
+        }
+        overlay_paths(p, m).0
+    } else {
+        p.config_file.clone()
+    })
+}
+
+/// Parent command of `alias` as merged from the layers below `target`.
+pub fn parent_below(p: &paths::Paths, target: &Path, alias: &str) -> Result<Option<String>> {
     let machine = resolve_machine_id(p);
-    let (machine_path, local_path) = overlay_paths(p, &machine);
+    let mut layers = layer_files(p, &machine);
+    if !layers.iter().any(|l| l == target) {
+        // Another machine's overlay only sits on top of the base config
+        layers.truncate(1);
+    }
+    let mut blocks = Vec::new();
+    for layer in layers {
+        if layer == target {
+            break;
+        }
+        blocks = merge_blocks(blocks, parse_text(&fs::read_to_string(&layer)?)?);
+    }
+    Ok(blocks
+        .into_iter()
+        .find(|b| b.name == alias)
+        .map(|b| b.parent))
+}
 
-    if machine_path.exists() {
-        let overlay = std::fs::read_to_string(&machine_path)?;
-        let ob = parse_text(&overlay)?;
-        blocks = merge_blocks(blocks, ob);
+/// Short layer name for a config file: base, group, machine or local.
+pub fn layer_name(p: &paths::Paths, file: &Path) -> &'static str {
+    if file == p.config_file {
+        "base"
+    } else if file.starts_with(p.repo_path.join("machines").join("groups")) {
+        "group"
+    } else if file.starts_with(p.repo_path.join("machines")) {
+        "machine"
+    } else {
+        "local"
     }
-    if local_path.exists() {
-        let overlay = std::fs::read_to_string(&local_path)?;
-        let ob = parse_text(&overlay)?;
-        blocks = merge_blocks(blocks, ob);
+}
+
+/// Privilege settings from `@sudo:` and `@root:` directives; the last layer that sets one wins.
+pub fn resolve_privilege(p: &paths::Paths) -> Result<Privilege> {
+    privilege_from(&layer_files(p, &resolve_machine_id(p)))
+}
+
+pub fn privilege_from(layers: &[PathBuf]) -> Result<Privilege> {
+    privilege_from_sources(&read_layers(layers)?)
+}
+
+/// Machine-wide defaults from the `sudo` and `root` settings.
+fn privilege_defaults() -> Result<Privilege> {
+    let mut privilege = Privilege::default();
+    if let Some((value, source)) = crate::settings::lookup("sudo") {
+        privilege.tool = Escalation::parse(&value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}: unknown escalation tool '{}' (expected sudo, doas, run0 or none)",
+                source.describe(),
+                value
+            )
+        })?;
     }
-    // Legacy local overlay fallback: alf.local.conf
-    if !local_path.exists() {
-        let legacy_local = p.repo_path.join("alf.local.conf");
-        if legacy_local.exists() {
-            let overlay = std::fs::read_to_string(&legacy_local)?;
-            let ob = parse_text(&overlay)?;
-            blocks = merge_blocks(blocks, ob);
+    if let Some((value, source)) = crate::settings::lookup("root") {
+        privilege.root = RootPolicy::parse(&value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}: unknown root policy '{}' (expected auto, always or never)",
+                source.describe(),
+                value
+            )
+        })?;
+    }
+    Ok(privilege)
+}
+
+fn privilege_from_sources(sources: &[(PathBuf, String)]) -> Result<Privilege> {
+    let mut privilege = privilege_defaults()?;
+    for (layer, text) in sources {
+        for (key, value) in parse_directives(text)? {
+            match key.as_str() {
+                "sudo" => {
+                    privilege.tool = Escalation::parse(&value).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "{}: unknown escalation tool '{}' (expected sudo, doas, run0 or none)",
+                            layer.display(),
+                            value
+                        )
+                    })?;
+                }
+                "root" => {
+                    privilege.root = RootPolicy::parse(&value).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "{}: unknown root policy '{}' (expected auto, always or never)",
+                            layer.display(),
+                            value
+                        )
+                    })?;
+                }
+                _ => {}
+            }
         }
     }
-    Ok(blocks)
+    Ok(privilege)
 }
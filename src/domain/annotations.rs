@@ -0,0 +1,78 @@
+use super::model::{AliasBlock, RootPolicy};
+
+/// Annotations recognised at the start of an alias command, e.g. `upd: @root apt update`.
+pub const KNOWN: &[&str] = &["root", "shadow-ok"];
+
+/// Split leading `@annotation` tokens off a command.
+pub fn split_annotations(cmd: &str) -> (Vec<&str>, &str) {
+    let mut found = Vec::new();
+    let mut rest = cmd.trim_start();
+    while let Some(tail) = rest.strip_prefix('@') {
+        let end = tail.find(char::is_whitespace).unwrap_or(tail.len());
+        let name = &tail[..end];
+        if !KNOWN.contains(&name) {
+            break;
+        }
+        found.push(name);
+        rest = tail[end..].trim_start();
+    }
+    (found, rest)
+}
+
+pub fn has_annotation(cmd: &str, name: &str) -> bool {
+    split_annotations(cmd).0.contains(&name)
+}
+
+fn with_sudo(cmd: &str) -> String {
+    if cmd.starts_with("sudo ") {
+        cmd.to_string()
+    } else {
+        format!("sudo {}", cmd)
+    }
+}
+
+/// Strip annotations and turn `@root` into a `sudo` prefix according to the policy.
+/// Subcommands that need root under a plain parent become standalone (`!sudo parent sub`).
+pub fn resolve_annotations(blocks: Vec<AliasBlock>, policy: RootPolicy) -> Vec<AliasBlock> {
+    let escalate = policy != RootPolicy::Never;
+    blocks
+        .into_iter()
+        .map(|b| {
+            let (pflags, pbody) = split_annotations(&b.parent);
+            let parent_root = escalate && pflags.contains(&"root");
+            let parent_standalone = pbody.starts_with('!');
+            let subs = b
+                .subs
+                .iter()
+                .map(|(name, cmd)| {
+                    let (sflags, sbody) = split_annotations(cmd);
+                    let sub_root = escalate && sflags.contains(&"root");
+                    // A standalone `!` sub only needs root when it says so itself
+                    let own_cmd = sbody.starts_with('!');
+                    let needs_root = sub_root || (parent_root && !own_cmd);
+                    if !needs_root || (parent_root && !parent_standalone && !own_cmd) {
+                        return (name.clone(), sbody.to_string());
+                    }
+                    let combined = if let Some(own) = sbody.strip_prefix('!') {
+                        own.to_string()
+                    } else if parent_standalone {
+                        sbody.to_string()
+                    } else {
+                        format!("{} {}", pbody, sbody)
+                    };
+                    (name.clone(), format!("!{}", with_sudo(&combined)))
+                })
+                .collect();
+            let parent = if parent_root && !parent_standalone {
+                with_sudo(pbody)
+            } else {
+                pbody.to_string()
+            };
+            AliasBlock {
+                name: b.name,
+                parent,
+                subs,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+use super::model::AliasBlock;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One alias or subcommand that differs between two merged models.
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub alias: String,
+    pub sub: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl Change {
+    /// `alias` or `alias sub`
+    pub fn name(&self) -> String {
+        match &self.sub {
+            Some(s) => format!("{} {}", self.alias, s),
+            None => self.alias.clone(),
+        }
+    }
+}
+
+/// Changes that turn `old` into `new`, keyed by alias and sub name.
+/// Follows the order of `new`, with removals after the entries they preceded.
+pub fn diff_blocks(old: &[AliasBlock], new: &[AliasBlock]) -> Vec<Change> {
+    let mut out = Vec::new();
+    let change = |kind, alias: &str, sub: Option<&str>, o: Option<&str>, n: Option<&str>| Change {
+        kind,
+        alias: alias.to_string(),
+        sub: sub.map(str::to_string),
+        old: o.map(str::to_string),
+        new: n.map(str::to_string),
+    };
+    for nb in new {
+        let Some(ob) = old.iter().find(|b| b.name == nb.name) else {
+            out.push(change(
+                ChangeKind::Added,
+                &nb.name,
+                None,
+                None,
+                Some(&nb.parent),
+            ));
+            for (s, c) in &nb.subs {
+                out.push(change(ChangeKind::Added, &nb.name, Some(s), None, Some(c)));
+            }
+            continue;
+        };
+        if ob.parent != nb.parent {
+            out.push(change(
+                ChangeKind::Changed,
+                &nb.name,
+                None,
+                Some(&ob.parent),
+                Some(&nb.parent),
+            ));
+        }
+        for (s, c) in &nb.subs {
+            match ob.subs.iter().find(|(n, _)| n == s) {
+                None => out.push(change(ChangeKind::Added, &nb.name, Some(s), None, Some(c))),
+                Some((_, oc)) if oc != c => out.push(change(
+                    ChangeKind::Changed,
+                    &nb.name,
+                    Some(s),
+                    Some(oc),
+                    Some(c),
+                )),
+                Some(_) => {}
+            }
+        }
+        for (s, c) in &ob.subs {
+            if !nb.subs.iter().any(|(n, _)| n == s) {
+                out.push(change(
+                    ChangeKind::Removed,
+                    &nb.name,
+                    Some(s),
+                    Some(c),
+                    None,
+                ));
+            }
+        }
+    }
+    for ob in old {
+        if new.iter().any(|b| b.name == ob.name) {
+            continue;
+        }
+        out.push(change(
+            ChangeKind::Removed,
+            &ob.name,
+            None,
+            Some(&ob.parent),
+            None,
+        ));
+        for (s, c) in &ob.subs {
+            out.push(change(
+                ChangeKind::Removed,
+                &ob.name,
+                Some(s),
+                Some(c),
+                None,
+            ));
+        }
+    }
+    out
+}
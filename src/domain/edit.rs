@@ -0,0 +1,153 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+
+fn line_re() -> Regex {
+    Regex::new(r"^( *)([A-Za-z0-9\-]+):( *)(.*)$").unwrap()
+}
+
+pub fn valid_name(name: &str) -> bool {
+    Regex::new(r"^[A-Za-z0-9\-]+$").unwrap().is_match(name)
+}
+
+/// A config layer edited line by line, so comments and formatting survive.
+struct Doc {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+impl Doc {
+    fn parse(text: &str) -> Doc {
+        Doc {
+            lines: text.lines().map(str::to_string).collect(),
+            trailing_newline: text.is_empty() || text.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = self.lines.join("\n");
+        if self.trailing_newline && !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+
+    fn top_level(&self, alias: &str) -> Option<usize> {
+        let re = line_re();
+        self.lines.iter().position(|l| {
+            re.captures(l)
+                .map(|c| c[1].is_empty() && &c[2] == alias)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Index of the last sub line belonging to the alias at `start`.
+    fn block_end(&self, start: usize) -> usize {
+        let re = line_re();
+        let mut end = start;
+        for (i, l) in self.lines.iter().enumerate().skip(start + 1) {
+            let t = l.trim_start();
+            if t.is_empty() || t.starts_with('#') {
+                continue;
+            }
+            match re.captures(l) {
+                Some(c) if !c[1].is_empty() => end = i,
+                _ => break,
+            }
+        }
+        end
+    }
+
+    fn sub(&self, start: usize, sub: &str) -> Option<usize> {
+        let re = line_re();
+        (start + 1..=self.block_end(start)).find(|&i| {
+            re.captures(&self.lines[i])
+                .map(|c| !c[1].is_empty() && &c[2] == sub)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Replace the command on line `i`, keeping its indentation and spacing.
+    fn replace_command(&mut self, i: usize, cmd: &str) {
+        let re = line_re();
+        let c = re.captures(&self.lines[i]).unwrap();
+        let sep = if c[3].is_empty() { " " } else { &c[3] };
+        self.lines[i] = format!("{}{}:{}{}", &c[1], &c[2], sep, cmd);
+    }
+
+    /// Remove lines `from..=to` plus the comment lines directly above them.
+    fn remove_with_comments(&mut self, from: usize, to: usize) {
+        let mut first = from;
+        while first > 0 && self.lines[first - 1].trim_start().starts_with('#') {
+            first -= 1;
+        }
+        self.lines.drain(first..=to);
+    }
+
+    fn append(&mut self, line: String) {
+        self.lines.push(line);
+        self.trailing_newline = true;
+    }
+}
+
+pub fn has_alias(text: &str, alias: &str) -> bool {
+    Doc::parse(text).top_level(alias).is_some()
+}
+
+pub fn has_sub(text: &str, alias: &str, sub: &str) -> bool {
+    let doc = Doc::parse(text);
+    doc.top_level(alias)
+        .and_then(|start| doc.sub(start, sub))
+        .is_some()
+}
+
+/// Set the parent command of `alias`, appending a new alias when missing.
+pub fn set_parent(text: &str, alias: &str, cmd: &str) -> String {
+    let mut doc = Doc::parse(text);
+    match doc.top_level(alias) {
+        Some(i) => doc.replace_command(i, cmd),
+        None => doc.append(format!("{}: {}", alias, cmd)),
+    }
+    doc.render()
+}
+
+/// Set a subcommand of `alias`; the alias line must already exist.
+pub fn set_sub(text: &str, alias: &str, sub: &str, cmd: &str) -> Result<String> {
+    let mut doc = Doc::parse(text);
+    let Some(start) = doc.top_level(alias) else {
+        bail!("no alias '{}' in this file", alias);
+    };
+    if let Some(i) = doc.sub(start, sub) {
+        doc.replace_command(i, cmd);
+        return Ok(doc.render());
+    }
+    let end = doc.block_end(start);
+    let indent = if end > start {
+        let re = line_re();
+        re.captures(&doc.lines[end]).unwrap()[1].to_string()
+    } else {
+        "  ".to_string()
+    };
+    doc.lines
+        .insert(end + 1, format!("{}{}: {}", indent, sub, cmd));
+    Ok(doc.render())
+}
+
+/// Remove `alias` with all its subcommands.
+pub fn remove_alias(text: &str, alias: &str) -> Result<String> {
+    let mut doc = Doc::parse(text);
+    let Some(start) = doc.top_level(alias) else {
+        bail!("no alias '{}' in this file", alias);
+    };
+    let end = doc.block_end(start);
+    doc.remove_with_comments(start, end);
+    Ok(doc.render())
+}
+
+pub fn remove_sub(text: &str, alias: &str, sub: &str) -> Result<String> {
+    let mut doc = Doc::parse(text);
+    let Some(i) = doc.top_level(alias).and_then(|start| doc.sub(start, sub)) else {
+        bail!("no subcommand '{} {}' in this file", alias, sub);
+    };
+    doc.remove_with_comments(i, i);
+    Ok(doc.render())
+}
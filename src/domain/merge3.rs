@@ -0,0 +1,216 @@
+use super::edit;
+use super::model::AliasBlock;
+use super::parse::{parse_directives, parse_text};
+use anyhow::Result;
+
+/// An alias, sub or directive both sides changed in different ways.
+/// `None` means that side does not have it.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub alias: String,
+    pub sub: Option<String>,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    /// Their subcommands, restored with the alias when their side is taken
+    /// for an alias we removed
+    pub theirs_subs: Vec<(String, String)>,
+}
+
+impl Conflict {
+    /// `alias`, `alias sub` or `@directive`
+    pub fn name(&self) -> String {
+        match &self.sub {
+            Some(s) => format!("{} {}", self.alias, s),
+            None => self.alias.clone(),
+        }
+    }
+}
+
+/// Merge a config layer keyed by alias and sub name. `ours` keeps its
+/// comments and layout; changes made only in `theirs` are applied on top.
+/// Conflicting entries keep our value and are returned for resolution.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Result<(String, Vec<Conflict>)> {
+    let (b, o, t) = (parse_text(base)?, parse_text(ours)?, parse_text(theirs)?);
+    let mut text = ours.to_string();
+    let mut conflicts = Vec::new();
+    let find = |blocks: &[AliasBlock], name: &str| blocks.iter().find(|x| x.name == name).cloned();
+    let mut names: Vec<&String> = t.iter().map(|x| &x.name).collect();
+    names.extend(b.iter().map(|x| &x.name).filter(|n| find(&t, n).is_none()));
+
+    for name in names {
+        let (bb, ob, tb) = (find(&b, name), find(&o, name), find(&t, name));
+        if same(bb.as_ref(), tb.as_ref()) {
+            continue;
+        }
+        let whole = |ours: Option<&AliasBlock>, theirs: Option<&AliasBlock>| Conflict {
+            alias: name.clone(),
+            sub: None,
+            base: bb.as_ref().map(|x| x.parent.clone()),
+            ours: ours.map(|x| x.parent.clone()),
+            theirs: theirs.map(|x| x.parent.clone()),
+            theirs_subs: theirs.map(|x| x.subs.clone()).unwrap_or_default(),
+        };
+        match (&ob, &tb) {
+            // Gone on both sides
+            (None, None) => {}
+            // Removed by them: fine unless we changed the block meanwhile
+            (Some(ob), None) if same(Some(ob), bb.as_ref()) => {
+                text = edit::remove_alias(&text, name)?;
+            }
+            (Some(ob), None) => conflicts.push(whole(Some(ob), None)),
+            // New on their side only
+            (None, Some(tb)) if bb.is_none() => text = add_block(&text, tb)?,
+            // Removed by us, changed by them
+            (None, Some(tb)) => conflicts.push(whole(None, Some(tb))),
+            (Some(ob), Some(tb)) => {
+                let mut entries: Vec<Option<&str>> = vec![None];
+                for (s, _) in tb.subs.iter().chain(bb.iter().flat_map(|x| x.subs.iter())) {
+                    if !entries.contains(&Some(s.as_str())) {
+                        entries.push(Some(s));
+                    }
+                }
+                for sub in entries {
+                    let bv = bb.as_ref().and_then(|x| entry(x, sub));
+                    let (ov, tv) = (entry(ob, sub), entry(tb, sub));
+                    if tv == bv || ov == tv {
+                        continue;
+                    }
+                    if ov == bv {
+                        text = apply(&text, name, sub, tv.as_deref())?;
+                    } else {
+                        conflicts.push(Conflict {
+                            alias: name.clone(),
+                            sub: sub.map(str::to_string),
+                            base: bv,
+                            ours: ov,
+                            theirs: tv,
+                            theirs_subs: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let (bd, od, td) = (
+        parse_directives(base)?,
+        parse_directives(ours)?,
+        parse_directives(theirs)?,
+    );
+    let last = |d: &[(String, String)], key: &str| {
+        d.iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+    let mut keys: Vec<&String> = td.iter().chain(bd.iter()).map(|(k, _)| k).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (bv, ov, tv) = (last(&bd, key), last(&od, key), last(&td, key));
+        if tv == bv || ov == tv {
+            continue;
+        }
+        if ov == bv {
+            text = set_directive(&text, key, tv.as_deref());
+        } else {
+            conflicts.push(Conflict {
+                alias: format!("@{}", key),
+                sub: None,
+                base: bv,
+                ours: ov,
+                theirs: tv,
+                theirs_subs: Vec::new(),
+            });
+        }
+    }
+    Ok((text, conflicts))
+}
+
+/// Parent command (`sub` None) or a subcommand of `block`.
+fn entry(block: &AliasBlock, sub: Option<&str>) -> Option<String> {
+    match sub {
+        None => Some(block.parent.clone()),
+        Some(s) => block
+            .subs
+            .iter()
+            .find(|(n, _)| n == s)
+            .map(|(_, c)| c.clone()),
+    }
+}
+
+/// Same parent and subcommands, ignoring sub order.
+fn same(a: Option<&AliasBlock>, b: Option<&AliasBlock>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            a.parent == b.parent
+                && a.subs.len() == b.subs.len()
+                && a.subs.iter().all(|s| b.subs.contains(s))
+        }
+        _ => false,
+    }
+}
+
+fn add_block(text: &str, block: &AliasBlock) -> Result<String> {
+    let mut text = edit::set_parent(text, &block.name, &block.parent);
+    for (s, cmd) in &block.subs {
+        text = edit::set_sub(&text, &block.name, s, cmd)?;
+    }
+    Ok(text)
+}
+
+/// Put their side of a conflict into `text`.
+pub fn take_theirs(text: &str, conflict: &Conflict) -> Result<String> {
+    match conflict.alias.strip_prefix('@') {
+        Some(key) => Ok(set_directive(text, key, conflict.theirs.as_deref())),
+        None if conflict.sub.is_none() && !edit::has_alias(text, &conflict.alias) => {
+            match &conflict.theirs {
+                Some(parent) => add_block(
+                    text,
+                    &AliasBlock {
+                        name: conflict.alias.clone(),
+                        parent: parent.clone(),
+                        subs: conflict.theirs_subs.clone(),
+                    },
+                ),
+                None => Ok(text.to_string()),
+            }
+        }
+        None => apply(
+            text,
+            &conflict.alias,
+            conflict.sub.as_deref(),
+            conflict.theirs.as_deref(),
+        ),
+    }
+}
+
+fn apply(text: &str, alias: &str, sub: Option<&str>, new: Option<&str>) -> Result<String> {
+    match (sub, new) {
+        (None, Some(cmd)) => Ok(edit::set_parent(text, alias, cmd)),
+        (None, None) => edit::remove_alias(text, alias),
+        (Some(s), Some(cmd)) => edit::set_sub(text, alias, s, cmd),
+        (Some(s), None) if edit::has_sub(text, alias, s) => edit::remove_sub(text, alias, s),
+        (Some(_), None) => Ok(text.to_string()),
+    }
+}
+
+/// Replace (or add at the top, or drop) the `@key:` line of a layer.
+fn set_directive(text: &str, key: &str, value: Option<&str>) -> String {
+    let prefix = format!("@{}:", key);
+    let mut lines: Vec<String> = text
+        .lines()
+        .filter(|l| !l.to_lowercase().starts_with(&prefix))
+        .map(str::to_string)
+        .collect();
+    if let Some(v) = value {
+        lines.insert(0, format!("@{}: {}", key, v));
+    }
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
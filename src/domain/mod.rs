@@ -1,7 +1,14 @@
+pub mod annotations;
+pub mod diff;
+pub mod edit;
 pub mod merge;
+pub mod merge3;
 pub mod model;
 pub mod parse;
 
+pub use annotations::resolve_annotations;
+pub use diff::{diff_blocks, Change, ChangeKind};
 pub use merge::{merge_blocks, serialize_blocks};
-pub use model::AliasBlock;
-pub use parse::parse_text;
+pub use merge3::{merge3, take_theirs, Conflict};
+pub use model::{AliasBlock, Definition, Escalation, Privilege, RootPolicy};
+pub use parse::{parse_definitions, parse_directives, parse_text, validate_text};
@@ -1,6 +1,117 @@
+use std::path::PathBuf;
+
 #[derive(Clone, Debug)]
 pub struct AliasBlock {
     pub name: String,
     pub parent: String,
     pub subs: Vec<(String, String)>,
 }
+
+impl AliasBlock {
+    /// Command behind `name [sub]` as `ralf which` prints it, or None for an unknown sub.
+    pub fn command_for(&self, sub: Option<&str>) -> Option<String> {
+        let Some(sname) = sub else {
+            return Some(self.parent.clone());
+        };
+        let (_, scmd) = self.subs.iter().find(|(n, _)| n == sname)?;
+        Some(if let Some(own) = scmd.strip_prefix('!') {
+            own.to_string()
+        } else if self.parent.starts_with('!') {
+            scmd.clone()
+        } else {
+            format!("{} {}", self.parent, scmd)
+        })
+    }
+}
+
+/// One `name: command` line of a config layer, with where it was found.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub file: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    pub alias: String,
+    /// Subcommand name for indented lines
+    pub sub: Option<String>,
+    pub command: String,
+    /// Comment lines directly above the definition
+    pub description: Option<String>,
+}
+
+/// Privilege escalation tool used by the generated `__ralf_sudo` wrapper.
+/// Set per layer with an `@sudo: <tool>` directive; later layers win.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Escalation {
+    #[default]
+    Sudo,
+    Doas,
+    Run0,
+    /// Run the command as-is (for machines where you already are root)
+    None,
+}
+
+impl Escalation {
+    pub fn parse(s: &str) -> Option<Escalation> {
+        match s.trim().to_lowercase().as_str() {
+            "sudo" => Some(Escalation::Sudo),
+            "doas" => Some(Escalation::Doas),
+            "run0" => Some(Escalation::Run0),
+            "none" => Some(Escalation::None),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Escalation::Sudo => "sudo",
+            Escalation::Doas => "doas",
+            Escalation::Run0 => "run0",
+            Escalation::None => "none",
+        }
+    }
+
+    /// Flag that makes the tool fail instead of prompting for a password.
+    pub fn non_interactive_flag(&self) -> &'static str {
+        match self {
+            Escalation::Sudo | Escalation::Doas => "-n",
+            Escalation::Run0 => "--no-ask-password",
+            Escalation::None => "",
+        }
+    }
+}
+
+/// How `@root` aliases are run on a machine. Set with an `@root:` directive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RootPolicy {
+    /// Escalate unless the shell already runs as root
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl RootPolicy {
+    pub fn parse(s: &str) -> Option<RootPolicy> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" | "when-not-root" => Some(RootPolicy::Auto),
+            "always" => Some(RootPolicy::Always),
+            "never" => Some(RootPolicy::Never),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RootPolicy::Auto => "auto",
+            RootPolicy::Always => "always",
+            RootPolicy::Never => "never",
+        }
+    }
+}
+
+/// Effective privilege settings for the current machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Privilege {
+    pub tool: Escalation,
+    pub root: RootPolicy,
+}
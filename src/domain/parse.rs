@@ -1,7 +1,8 @@
 use anyhow::Result;
 use regex::Regex;
+use std::path::Path;
 
-use super::model::AliasBlock;
+use super::model::{AliasBlock, Definition};
 
 pub fn parse_text(text: &str) -> Result<Vec<AliasBlock>> {
     let re = Regex::new(r"^( *)([A-Za-z0-9\-]+): *(.+)$")?;
@@ -35,3 +36,127 @@ pub fn parse_text(text: &str) -> Result<Vec<AliasBlock>> {
     }
     Ok(blocks)
 }
+
+/// Same grammar as `parse_text`, but keeps each line's location.
+pub fn parse_definitions(text: &str, file: &Path) -> Result<Vec<Definition>> {
+    let re = Regex::new(r"^( *)([A-Za-z0-9\-]+): *(.+)$")?;
+    let mut out = Vec::new();
+    let mut current: Option<String> = None;
+    let mut comment: Vec<String> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if let Some(rest) = line.trim_start().strip_prefix('#') {
+            comment.push(rest.trim().to_string());
+            continue;
+        }
+        let description = Some(comment.join(" ")).filter(|d| !d.is_empty());
+        comment.clear();
+        let Some(c) = re.captures(line) else { continue };
+        let name = c.get(2).unwrap().as_str().to_string();
+        let command = c.get(3).unwrap().as_str().to_string();
+        let (alias, sub) = if c.get(1).unwrap().as_str().is_empty() {
+            current = Some(name.clone());
+            (name, None)
+        } else if let Some(parent) = &current {
+            (parent.clone(), Some(name))
+        } else {
+            continue;
+        };
+        out.push(Definition {
+            file: file.to_path_buf(),
+            line: i + 1,
+            alias,
+            sub,
+            command,
+            description,
+        });
+    }
+    Ok(out)
+}
+
+/// Layer directives are top-level lines of the form `@name: value`.
+/// They are invisible to `parse_text`, so older ralf versions ignore them.
+pub fn parse_directives(text: &str) -> Result<Vec<(String, String)>> {
+    let re = Regex::new(r"^@([A-Za-z0-9\-]+): *(.+)$")?;
+    let mut out = Vec::new();
+    for line in text.lines() {
+        if let Some(c) = re.captures(line) {
+            out.push((
+                c.get(1).unwrap().as_str().to_lowercase(),
+                c.get(2).unwrap().as_str().trim().to_string(),
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Lines of a config layer the parser would silently ignore or misread,
+/// as (1-based line, message) pairs.
+pub fn validate_text(text: &str) -> Result<Vec<(usize, String)>> {
+    let re = Regex::new(r"^( *)([A-Za-z0-9\-]+): *(.+)$")?;
+    let mut problems = Vec::new();
+    let mut aliases: Vec<String> = Vec::new();
+    let mut subs: Vec<String> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if ["<<<<<<<", "=======", ">>>>>>>", "|||||||"]
+            .iter()
+            .any(|m| line.starts_with(m))
+        {
+            problems.push((n, "unresolved merge conflict marker".to_string()));
+            continue;
+        }
+        if line.starts_with('@') {
+            let directives = parse_directives(line)?;
+            if directives.is_empty() {
+                problems.push((n, "expected '@name: value'".to_string()));
+            }
+            for (key, value) in directives {
+                let ok = match key.as_str() {
+                    "sudo" => super::Escalation::parse(&value).is_some(),
+                    "root" => super::RootPolicy::parse(&value).is_some(),
+                    _ => {
+                        problems.push((n, format!("unknown directive '@{}'", key)));
+                        continue;
+                    }
+                };
+                if !ok {
+                    problems.push((n, format!("invalid value '{}' for @{}", value, key)));
+                }
+            }
+            continue;
+        }
+        if line.starts_with('\t') {
+            problems.push((n, "indent subcommands with spaces, not tabs".to_string()));
+            continue;
+        }
+        let Some(c) = re.captures(line) else {
+            let expected = if line.starts_with(' ') {
+                "expected '  sub: command'"
+            } else {
+                "expected 'name: command'"
+            };
+            problems.push((n, expected.to_string()));
+            continue;
+        };
+        let name = c.get(2).unwrap().as_str().to_string();
+        if c.get(1).unwrap().as_str().is_empty() {
+            if aliases.contains(&name) {
+                problems.push((n, format!("alias '{}' is defined twice", name)));
+            }
+            aliases.push(name);
+            subs.clear();
+        } else if let Some(parent) = aliases.last() {
+            if subs.contains(&name) {
+                problems.push((n, format!("sub '{} {}' is defined twice", parent, name)));
+            }
+            subs.push(name);
+        } else {
+            problems.push((n, format!("sub '{}' has no parent alias above it", name)));
+        }
+    }
+    Ok(problems)
+}
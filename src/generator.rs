@@ -1,13 +1,43 @@
+use crate::domain::{AliasBlock, Escalation, Privilege, RootPolicy};
 use crate::paths;
 use regex::Regex;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 enum State {
     Simple,
     Nested,
 }
 
+/// Per-machine settings that shape the generated output.
+#[derive(Clone, Debug, Default)]
+pub struct GenOptions {
+    pub privilege: Privilege,
+    /// When set, generated functions append each call to the usage log.
+    pub usage: Option<UsageLog>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UsageLog {
+    pub file: PathBuf,
+    pub machine: String,
+}
+
+pub fn options_for(p: &paths::Paths) -> anyhow::Result<GenOptions> {
+    let usage = if crate::cmd_stats::enabled() {
+        Some(UsageLog {
+            file: crate::cmd_stats::log_file(),
+            machine: crate::config_merge::resolve_machine_id(p),
+        })
+    } else {
+        None
+    };
+    Ok(GenOptions {
+        privilege: crate::config_merge::resolve_privilege(p)?,
+        usage,
+    })
+}
+
 pub fn is_reserved_fish(name: &str) -> bool {
     matches!(
         name,
@@ -26,26 +56,89 @@ pub fn is_reserved_fish(name: &str) -> bool {
 pub fn generate_config() -> anyhow::Result<String> {
     let p = paths::find_config_or_exit()?;
     let text = crate::config_merge::load_and_merge(&p)?;
+    let opts = options_for(&p)?;
+    match target_shell(&p.aliases_file) {
+        Shell::Fish => generate_config_fish_from_text(&text, &opts),
+        Shell::Sh => generate_config_sh_from_text(&text, &opts),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    /// bash and zsh share the same output
+    Sh,
+    Fish,
+}
+
+impl Shell {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Shell::Sh => "bash/zsh",
+            Shell::Fish => "fish",
+        }
+    }
+}
+
+/// Shell the aliases file is generated for, from the environment or the file name.
+pub fn target_shell(aliases_file: &Path) -> Shell {
     let is_fish = std::env::var("FISH_VERSION").is_ok()
         || std::env::var("SHELL")
             .ok()
             .map(|s| s.ends_with("fish") || s.contains("/fish"))
             .unwrap_or(false)
         || {
-            let s = p.aliases_file.to_string_lossy();
+            let s = aliases_file.to_string_lossy();
             s.ends_with(".fish") || s.contains("/fish/")
         };
     if is_fish {
-        generate_config_fish_from_text(&text)
+        Shell::Fish
     } else {
-        generate_config_sh_from_text(&text)
+        Shell::Sh
     }
 }
 
-pub(crate) fn generate_config_sh_from_text(text: &str) -> anyhow::Result<String> {
+/// The command line the generated function runs for `alias [sub]` in `shell`,
+/// or None when nothing is generated for it.
+pub fn expansion(
+    block: &AliasBlock,
+    sub: Option<&str>,
+    shell: Shell,
+) -> anyhow::Result<Option<String>> {
+    if shell == Shell::Fish && is_reserved_fish(&block.name) {
+        return Ok(None);
+    }
+    let parent = rewrite_self_reference(&block.name, &block.parent)?;
+    let line = match sub {
+        Some(sname) => {
+            let Some((_, scmd)) = block.subs.iter().find(|(n, _)| n == sname) else {
+                return Ok(None);
+            };
+            match shell {
+                Shell::Sh => sh_sub_line(&parent, scmd),
+                Shell::Fish => fish_sub_line(&parent, scmd),
+            }
+        }
+        None => match shell {
+            Shell::Sh => sh_parent_line(&parent),
+            Shell::Fish => fish_parent_line(&parent),
+        },
+    };
+    Ok(Some(line))
+}
+
+pub(crate) fn generate_config_sh_from_text(
+    text: &str,
+    opts: &GenOptions,
+) -> anyhow::Result<String> {
     let re = Regex::new(r"^( *)([A-Za-z0-9\-]+): *(.+)$")?;
     let mut out = String::new();
+    out.push_str(&sudo_wrapper_sh(opts.privilege));
+    if let Some(usage) = &opts.usage {
+        out.push_str(&usage_logger_sh(usage));
+    }
+    let log = opts.usage.is_some();
     let mut lastcmd: Option<String> = None;
+    let mut lastalias = String::new();
     let mut state = State::Simple;
     let mut case_open = false;
     for line in text.lines() {
@@ -54,43 +147,43 @@ pub(crate) fn generate_config_sh_from_text(text: &str) -> anyhow::Result<String>
             let alias = caps.get(2).unwrap().as_str().to_string();
             let mut cmd = caps.get(3).unwrap().as_str().to_string();
             if indent.is_empty() {
-                generate_last_cmd(&mut out, lastcmd.take(), &mut state, &mut case_open);
-                let local_re = Regex::new(&format!(r"^{}( +|$)", regex::escape(&alias)))?;
-                if local_re.is_match(&cmd) {
-                    cmd = format!("command {}", cmd);
-                }
+                generate_last_cmd(
+                    &mut out,
+                    lastcmd.take(),
+                    log.then_some(lastalias.as_str()),
+                    &mut state,
+                    &mut case_open,
+                );
+                cmd = rewrite_self_reference(&alias, &cmd)?;
                 out.push_str(&format!(
                     "\nunalias {} 1>/dev/null 2>&1\n{}() {{\n",
                     alias, alias
                 ));
                 lastcmd = Some(cmd);
+                lastalias = alias;
             } else {
                 if let State::Simple = state {
                     out.push_str("  case \"$1\" in\n");
                     state = State::Nested;
                     case_open = true;
                 }
-                let ali2 = alias;
-                let cmd2 = cmd.clone();
-                out.push_str(&format!("    {})\n      shift\n", ali2));
-                let parent = lastcmd.as_ref().cloned().unwrap_or_default();
-                let combined = if cmd2.starts_with('!') {
-                    cmd2[1..].to_string()
-                } else if parent.starts_with('!') {
-                    cmd2.clone()
-                } else {
-                    format!("{} {}", parent, cmd2)
-                };
-                if cmd2.contains('$') {
-                    out.push_str(&format!("      {}\n", combined));
-                } else {
-                    out.push_str(&format!("      {} \"$@\"\n", combined));
+                out.push_str(&format!("    {})\n      shift\n", alias));
+                if log {
+                    out.push_str(&format!("      __ralf_log {} {}\n", lastalias, alias));
                 }
+                let parent = lastcmd.as_deref().unwrap_or_default();
+                out.push_str(&format!("      {}\n", sh_sub_line(parent, &cmd)));
                 out.push_str("      ;;\n");
             }
         }
     }
-    generate_last_cmd(&mut out, lastcmd.take(), &mut state, &mut case_open);
+    generate_last_cmd(
+        &mut out,
+        lastcmd.take(),
+        log.then_some(lastalias.as_str()),
+        &mut state,
+        &mut case_open,
+    );
     out.push('\n');
     if has_subcommands_text(&text) {
         out.push_str(&crate::completions::generate_completions_from_text(&text)?);
@@ -98,54 +191,23 @@ pub(crate) fn generate_config_sh_from_text(text: &str) -> anyhow::Result<String>
     Ok(out)
 }
 
-pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<String> {
+pub(crate) fn generate_config_fish_from_text(
+    text: &str,
+    opts: &GenOptions,
+) -> anyhow::Result<String> {
     let re = Regex::new(r"^( *)([A-Za-z0-9\-]+): *(.+)$")?;
     let mut out = String::new();
     out.push_str("# This file was automatically generated by ralf\n");
     out.push_str("# https://github.com/dannyben/ralf\n");
-    out.push_str("functions -q __ralf_sudo; and functions -e __ralf_sudo\n");
-    out.push_str("function __ralf_sudo\n");
-    out.push_str("  # Try non-interactive first\n");
-    out.push_str("  sudo -n $argv 2>/dev/null\n");
-    out.push_str("  set -l code $status\n");
-    out.push_str("  if test $code -eq 0\n");
-    out.push_str("    return 0\n");
-    out.push_str("  end\n");
-    out.push_str("  if test -n \"$RALF_SUDO_NO_PROMPT\"\n");
-    out.push_str(
-        "    echo \"sudo requires a password; skipping due to RALF_SUDO_NO_PROMPT\" >&2\n",
-    );
-    out.push_str("    return 1\n");
-    out.push_str("  end\n");
-    out.push_str("  if status --is-interactive\n");
-    out.push_str("    sudo $argv\n");
-    out.push_str("  else\n");
-    out.push_str("    echo \"sudo requires a password; cannot run non-interactively\" >&2\n");
-    out.push_str("    return 1\n");
-    out.push_str("  end\n");
-    out.push_str("end\n\n");
+    out.push_str(&sudo_wrapper_fish(opts.privilege));
+    if let Some(usage) = &opts.usage {
+        out.push_str(&usage_logger_fish(usage));
+    }
+    let log = opts.usage.is_some();
+    let mut lastalias = String::new();
     let mut lastcmd: Option<String> = None;
     let mut state = State::Simple;
     let mut skip_current = false;
-    let needs_bash_shim = |s: &str| {
-        s.contains("${")
-            || s.contains("$(")
-            || s.contains('`')
-            || s.contains("[[")
-            || s.contains("]]")
-            || s.contains("&&")
-            || s.contains("||")
-    };
-    let has_fish_arg_ref = |s: &str| s.contains("$argv") || s.contains("$rest");
-    let sq = |s: &str| s.replace('\'', "'\"'\"'");
-    let strip_leading_sudo = |s: &str| -> (bool, String) {
-        let t = s.trim_start();
-        if let Some(rest) = t.strip_prefix("sudo ") {
-            (true, rest.to_string())
-        } else {
-            (false, s.to_string())
-        }
-    };
     for line in text.lines() {
         if let Some(caps) = re.captures(line) {
             let indent = caps.get(1).unwrap().as_str();
@@ -154,31 +216,17 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
             if indent.is_empty() {
                 // Flush previous function
                 if let Some(last) = lastcmd.take() {
-                    let (was_sudo, base) = strip_leading_sudo(&last);
-                    let fullcmd = if base.starts_with('!') {
-                        "echo this alias requires a subcommand".to_string()
-                    } else if needs_bash_shim(&base) {
-                        let script = format!("{base} \"$@\"");
-                        let inner = format!("bash -lc '{}' -- $argv", sq(&script));
-                        if was_sudo {
-                            format!("__ralf_sudo {}", inner)
-                        } else {
-                            inner
-                        }
+                    let fullcmd = fish_parent_line(&last);
+                    let log_line = if log && !last.starts_with('!') {
+                        format!("__ralf_log {}", lastalias)
                     } else {
-                        let body = if has_fish_arg_ref(&base) {
-                            base
-                        } else {
-                            format!("{base} $argv")
-                        };
-                        if was_sudo {
-                            format!("__ralf_sudo {}", body)
-                        } else {
-                            body
-                        }
+                        String::new()
                     };
                     match state {
                         State::Simple => {
+                            if !log_line.is_empty() {
+                                out.push_str(&format!("  {}\n", log_line));
+                            }
                             out.push_str(&format!("  {}\nend\n", fullcmd));
                         }
                         State::Nested => {
@@ -186,6 +234,9 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
                                 "    case '*'
 ",
                             );
+                            if !log_line.is_empty() {
+                                out.push_str(&format!("      {}\n", log_line));
+                            }
                             out.push_str(&format!("      {}\n", fullcmd));
                             out.push_str("  end\n");
                             out.push_str("end\n");
@@ -200,15 +251,13 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
                     continue;
                 }
                 // Rewrite self-referencing commands
-                let local_re = Regex::new(&format!(r"^{}( +|$)", regex::escape(&alias)))?;
-                if local_re.is_match(&cmd) {
-                    cmd = format!("command {}", cmd);
-                }
+                cmd = rewrite_self_reference(&alias, &cmd)?;
                 out.push_str(&format!(
                     "\nfunctions -q {0}; and functions -e {0}\nfunction {0}\n",
                     alias
                 ));
                 lastcmd = Some(cmd);
+                lastalias = alias;
             } else {
                 if skip_current {
                     continue;
@@ -217,77 +266,33 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
                     out.push_str("  switch $argv[1]\n");
                     state = State::Nested;
                 }
-                let ali2 = alias;
-                let cmd2 = cmd.clone();
                 out.push_str(&format!(
                     "    case {}
 ",
-                    ali2
+                    alias
                 ));
                 out.push_str("      set -l rest $argv[2..-1]\n");
-                let parent = lastcmd.as_ref().cloned().unwrap_or_default();
-                let combined = if cmd2.starts_with('!') {
-                    cmd2[1..].to_string()
-                } else if parent.starts_with('!') {
-                    cmd2.clone()
-                } else {
-                    format!("{} {}", parent, cmd2)
-                };
-                let (was_sudo, base) = strip_leading_sudo(&combined);
-                let bashy = needs_bash_shim(&base);
-                if bashy {
-                    let script = format!("{base} \"$@\"");
-                    if was_sudo {
-                        out.push_str(&format!(
-                            "      __ralf_sudo bash -lc '{}' -- $rest\n",
-                            sq(&script)
-                        ));
-                    } else {
-                        out.push_str(&format!("      bash -lc '{}' -- $rest\n", sq(&script)));
-                    }
-                } else if has_fish_arg_ref(&base) {
-                    if was_sudo {
-                        out.push_str(&format!("      __ralf_sudo {}\n", base));
-                    } else {
-                        out.push_str(&format!("      {}\n", base));
-                    }
-                } else {
-                    if was_sudo {
-                        out.push_str(&format!("      __ralf_sudo {} $rest\n", base));
-                    } else {
-                        out.push_str(&format!("      {} $rest\n", base));
-                    }
+                if log {
+                    out.push_str(&format!("      __ralf_log {} {}\n", lastalias, alias));
                 }
+                let parent = lastcmd.as_deref().unwrap_or_default();
+                out.push_str(&format!("      {}\n", fish_sub_line(parent, &cmd)));
             }
         }
     }
     // Flush trailing
     if let Some(last) = lastcmd.take() {
-        let (was_sudo, base) = strip_leading_sudo(&last);
-        let fullcmd = if base.starts_with('!') {
-            "echo this alias requires a subcommand".to_string()
-        } else if needs_bash_shim(&base) {
-            let script = format!("{base} \"$@\"");
-            let inner = format!("bash -lc '{}' -- $argv", sq(&script));
-            if was_sudo {
-                format!("__ralf_sudo {}", inner)
-            } else {
-                inner
-            }
+        let fullcmd = fish_parent_line(&last);
+        let log_line = if log && !last.starts_with('!') {
+            format!("__ralf_log {}", lastalias)
         } else {
-            let body = if has_fish_arg_ref(&base) {
-                base
-            } else {
-                format!("{base} $argv")
-            };
-            if was_sudo {
-                format!("__ralf_sudo {}", body)
-            } else {
-                body
-            }
+            String::new()
         };
         match state {
             State::Simple => {
+                if !log_line.is_empty() {
+                    out.push_str(&format!("  {}\n", log_line));
+                }
                 out.push_str(&format!("  {}\nend\n", fullcmd));
             }
             State::Nested => {
@@ -295,6 +300,9 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
                     "    case '*'
 ",
                 );
+                if !log_line.is_empty() {
+                    out.push_str(&format!("      {}\n", log_line));
+                }
                 out.push_str(&format!("      {}\n", fullcmd));
                 out.push_str("  end\n");
                 out.push_str("end\n");
@@ -313,24 +321,29 @@ pub(crate) fn generate_config_fish_from_text(text: &str) -> anyhow::Result<Strin
 fn generate_last_cmd(
     out: &mut String,
     lastcmd: Option<String>,
+    log_alias: Option<&str>,
     state: &mut State,
     _case_open: &mut bool,
 ) {
     let Some(last) = lastcmd else { return };
-    let fullcmd = if last.starts_with('!') {
-        "echo this alias requires a subcommand".to_string()
-    } else if last.contains('$') {
-        last
-    } else {
-        format!("{last} \"$@\"")
+    let log_line = match log_alias {
+        Some(a) if !last.starts_with('!') => format!("__ralf_log {}", a),
+        _ => String::new(),
     };
+    let fullcmd = sh_parent_line(&last);
 
     match state {
         State::Simple => {
+            if !log_line.is_empty() {
+                out.push_str(&format!("  {}\n", log_line));
+            }
             out.push_str(&format!("  {}\n}}\n", fullcmd));
         }
         State::Nested => {
             out.push_str("    *)\n");
+            if !log_line.is_empty() {
+                out.push_str(&format!("      {}\n", log_line));
+            }
             out.push_str(&format!("      {}\n", fullcmd));
             out.push_str("      ;;\n");
             out.push_str("  esac\n");
@@ -340,6 +353,323 @@ fn generate_last_cmd(
     }
 }
 
+/// Rewrite commands that call the alias itself so they reach the real binary.
+fn rewrite_self_reference(alias: &str, cmd: &str) -> anyhow::Result<String> {
+    let local_re = Regex::new(&format!(r"^{}( +|$)", regex::escape(alias)))?;
+    if local_re.is_match(cmd) {
+        Ok(format!("command {}", cmd))
+    } else {
+        Ok(cmd.to_string())
+    }
+}
+
+/// Command a subcommand runs: `!` makes it standalone, as does a `!` parent.
+fn combine(parent: &str, sub: &str) -> String {
+    if let Some(own) = sub.strip_prefix('!') {
+        own.to_string()
+    } else if parent.starts_with('!') {
+        sub.to_string()
+    } else {
+        format!("{} {}", parent, sub)
+    }
+}
+
+fn sh_parent_line(last: &str) -> String {
+    if last.starts_with('!') {
+        "echo this alias requires a subcommand".to_string()
+    } else if last.contains('$') {
+        wrap_sudo_sh(last)
+    } else {
+        format!("{} \"$@\"", wrap_sudo_sh(last))
+    }
+}
+
+fn sh_sub_line(parent: &str, sub: &str) -> String {
+    let combined = wrap_sudo_sh(&combine(parent, sub));
+    if sub.contains('$') {
+        combined
+    } else {
+        format!("{} \"$@\"", combined)
+    }
+}
+
+fn needs_bash_shim(s: &str) -> bool {
+    s.contains("${")
+        || s.contains("$(")
+        || s.contains('`')
+        || s.contains("[[")
+        || s.contains("]]")
+        || s.contains("&&")
+        || s.contains("||")
+}
+
+fn has_fish_arg_ref(s: &str) -> bool {
+    s.contains("$argv") || s.contains("$rest")
+}
+
+fn sq(s: &str) -> String {
+    s.replace('\'', "'\"'\"'")
+}
+
+fn strip_leading_sudo(s: &str) -> (bool, String) {
+    let t = s.trim_start();
+    if let Some(rest) = t.strip_prefix("sudo ") {
+        (true, rest.to_string())
+    } else {
+        (false, s.to_string())
+    }
+}
+
+fn fish_parent_line(last: &str) -> String {
+    let (was_sudo, base) = strip_leading_sudo(last);
+    if base.starts_with('!') {
+        "echo this alias requires a subcommand".to_string()
+    } else if needs_bash_shim(&base) {
+        let script = format!("{base} \"$@\"");
+        let inner = format!("bash -lc '{}' -- $argv", sq(&script));
+        if was_sudo {
+            format!("__ralf_sudo {}", inner)
+        } else {
+            inner
+        }
+    } else {
+        let body = if has_fish_arg_ref(&base) {
+            base
+        } else {
+            format!("{base} $argv")
+        };
+        if was_sudo {
+            format!("__ralf_sudo {}", body)
+        } else {
+            body
+        }
+    }
+}
+
+fn fish_sub_line(parent: &str, sub: &str) -> String {
+    let (was_sudo, base) = strip_leading_sudo(&combine(parent, sub));
+    let prefix = if was_sudo { "__ralf_sudo " } else { "" };
+    if needs_bash_shim(&base) {
+        let script = format!("{base} \"$@\"");
+        format!("{}bash -lc '{}' -- $rest", prefix, sq(&script))
+    } else if has_fish_arg_ref(&base) {
+        format!("{}{}", prefix, base)
+    } else {
+        format!("{}{} $rest", prefix, base)
+    }
+}
+
+/// Route a leading `sudo ` through the generated `__ralf_sudo` wrapper.
+fn wrap_sudo_sh(cmd: &str) -> String {
+    match cmd.trim_start().strip_prefix("sudo ") {
+        Some(rest) => format!("__ralf_sudo {}", rest),
+        None => cmd.to_string(),
+    }
+}
+
+fn usage_logger_sh(usage: &UsageLog) -> String {
+    let file = usage.file.to_string_lossy().replace('\'', "'\"'\"'");
+    let mut out = String::new();
+    out.push_str("unset -f __ralf_log 1>/dev/null 2>&1\n");
+    out.push_str("__ralf_log() {\n");
+    out.push_str(&format!(
+        "  printf '%s\\t%s\\t%s\\t%s\\n' \"${{EPOCHSECONDS:-$(date +%s)}}\" '{}' \"$1\" \"$2\" >>'{}' 2>/dev/null\n",
+        usage.machine.replace('\'', ""),
+        file
+    ));
+    out.push_str("}\n");
+    out
+}
+
+fn usage_logger_fish(usage: &UsageLog) -> String {
+    let file = usage.file.to_string_lossy().replace('\'', "\\'");
+    let mut out = String::new();
+    out.push_str("functions -q __ralf_log; and functions -e __ralf_log\n");
+    out.push_str("function __ralf_log\n");
+    out.push_str(&format!(
+        "  printf '%s\\t%s\\t%s\\t%s\\n' (date +%s) '{}' \"$argv[1]\" \"$argv[2]\" >>'{}' 2>/dev/null\n",
+        usage.machine.replace('\'', ""),
+        file
+    ));
+    out.push_str("end\n\n");
+    out
+}
+
+fn sudo_wrapper_sh(privilege: Privilege) -> String {
+    let esc = privilege.tool;
+    let mut out = String::new();
+    out.push_str("unset -f __ralf_sudo 1>/dev/null 2>&1\n");
+    out.push_str("__ralf_sudo() {\n");
+    if esc != Escalation::None && privilege.root == RootPolicy::Auto {
+        // Already root: run the command itself, unless it passes the tool
+        // options (`sudo -u bob ...`) that only the tool understands
+        out.push_str("  if [ \"$(id -u)\" = 0 ] && [ \"${1#-}\" = \"$1\" ]; then\n");
+        out.push_str("    \"$@\"\n");
+        out.push_str("    return $?\n");
+        out.push_str("  fi\n");
+    }
+    if esc == Escalation::None {
+        out.push_str("  \"$@\"\n");
+        out.push_str("}\n");
+        return out;
+    }
+    let tool = esc.as_str();
+    out.push_str("  # Try non-interactive first\n");
+    out.push_str(&format!(
+        "  if {} {} true 1>/dev/null 2>&1; then\n",
+        tool,
+        esc.non_interactive_flag()
+    ));
+    out.push_str(&format!("    {} \"$@\"\n", tool));
+    out.push_str("    return $?\n");
+    out.push_str("  fi\n");
+    out.push_str("  if [ -n \"$RALF_SUDO_NO_PROMPT\" ]; then\n");
+    out.push_str(&format!(
+        "    echo \"{} requires a password; skipping due to RALF_SUDO_NO_PROMPT\" >&2\n",
+        tool
+    ));
+    out.push_str("    return 1\n");
+    out.push_str("  fi\n");
+    out.push_str("  case $- in\n");
+    out.push_str(&format!("    *i*) {} \"$@\" ;;\n", tool));
+    out.push_str("    *)\n");
+    out.push_str(&format!(
+        "      echo \"{} requires a password; cannot run non-interactively\" >&2\n",
+        tool
+    ));
+    out.push_str("      return 1\n");
+    out.push_str("      ;;\n");
+    out.push_str("  esac\n");
+    out.push_str("}\n");
+    out
+}
+
+fn sudo_wrapper_fish(privilege: Privilege) -> String {
+    let esc = privilege.tool;
+    let mut out = String::new();
+    out.push_str("functions -q __ralf_sudo; and functions -e __ralf_sudo\n");
+    out.push_str("function __ralf_sudo\n");
+    if esc != Escalation::None && privilege.root == RootPolicy::Auto {
+        out.push_str("  if test (id -u) = 0; and not string match -q -- '-*' $argv[1]\n");
+        out.push_str("    $argv\n");
+        out.push_str("    return $status\n");
+        out.push_str("  end\n");
+    }
+    if esc == Escalation::None {
+        out.push_str("  $argv\n");
+        out.push_str("end\n\n");
+        return out;
+    }
+    let tool = esc.as_str();
+    out.push_str("  # Try non-interactive first\n");
+    out.push_str(&format!(
+        "  if {} {} true 1>/dev/null 2>&1\n",
+        tool,
+        esc.non_interactive_flag()
+    ));
+    out.push_str(&format!("    {} $argv\n", tool));
+    out.push_str("    return $status\n");
+    out.push_str("  end\n");
+    out.push_str("  if test -n \"$RALF_SUDO_NO_PROMPT\"\n");
+    out.push_str(&format!(
+        "    echo \"{} requires a password; skipping due to RALF_SUDO_NO_PROMPT\" >&2\n",
+        tool
+    ));
+    out.push_str("    return 1\n");
+    out.push_str("  end\n");
+    out.push_str("  if status --is-interactive\n");
+    out.push_str(&format!("    {} $argv\n", tool));
+    out.push_str("  else\n");
+    out.push_str(&format!(
+        "    echo \"{} requires a password; cannot run non-interactively\" >&2\n",
+        tool
+    ));
+    out.push_str("    return 1\n");
+    out.push_str("  end\n");
+    out.push_str("end\n\n");
+    out
+}
+
+/// Generation id `ralf save` stamped into a generated file, if any.
+pub fn generation(text: &str) -> Option<String> {
+    let re = Regex::new(r#"RALF_GENERATION[= ]"([^"]*)""#).unwrap();
+    re.captures(text).map(|c| c[1].to_string())
+}
+
+/// Read the alias functions back out of generated output: each function's
+/// default branch becomes the parent and each `case` branch a sub. Helpers,
+/// completions and the environment block are skipped.
+pub fn parse_generated(text: &str, shell: Shell) -> Vec<AliasBlock> {
+    let (start, branch, end) = match shell {
+        Shell::Sh => (
+            Regex::new(r"^([A-Za-z0-9\-]+)\(\) \{$").unwrap(),
+            Regex::new(r"^    (\S+)\)$").unwrap(),
+            "}",
+        ),
+        Shell::Fish => (
+            Regex::new(r"^function ([A-Za-z0-9\-]+)$").unwrap(),
+            Regex::new(r"^    case (\S+)$").unwrap(),
+            "end",
+        ),
+    };
+    let noise = |l: &str| {
+        let t = l.trim();
+        t.is_empty()
+            || t == "shift"
+            || t == ";;"
+            || t == "esac"
+            || t == "end"
+            || t.starts_with("case \"$1\" in")
+            || t.starts_with("switch $argv[1]")
+            || t.starts_with("set -l rest ")
+            || t.starts_with("__ralf_log ")
+    };
+    let mut blocks: Vec<AliasBlock> = Vec::new();
+    let mut current: Option<(AliasBlock, Option<String>)> = None;
+    for line in text.lines() {
+        let Some((block, sub)) = current.as_mut() else {
+            if let Some(c) = start.captures(line) {
+                let name = c[1].to_string();
+                current = Some((
+                    AliasBlock {
+                        name,
+                        parent: String::new(),
+                        subs: Vec::new(),
+                    },
+                    None,
+                ));
+            }
+            continue;
+        };
+        if line == end {
+            if let Some((b, _)) = current.take() {
+                blocks.push(b);
+            }
+            continue;
+        }
+        if let Some(c) = branch.captures(line) {
+            let name = c[1].trim_matches('\'').to_string();
+            *sub = (name != "*").then_some(name);
+            if let Some(s) = sub.clone() {
+                block.subs.push((s, String::new()));
+            }
+            continue;
+        }
+        if noise(line) {
+            continue;
+        }
+        let target = match sub {
+            Some(_) => &mut block.subs.last_mut().unwrap().1,
+            None => &mut block.parent,
+        };
+        if !target.is_empty() {
+            target.push('\n');
+        }
+        target.push_str(line.trim());
+    }
+    blocks
+}
+
 #[allow(dead_code)]
 pub fn has_subcommands(config_file: &Path) -> anyhow::Result<bool> {
     let re = Regex::new(r"^ +([a-z0-9\-]+):")?;
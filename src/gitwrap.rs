@@ -32,10 +32,15 @@ pub fn ensure_ralf_gitignore(repo_path: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn clone(url: &str, dest: &Path) -> Result<()> {
+/// Clone `url` into `dest`, checking out `branch` instead of the remote's default.
+pub fn clone(url: &str, dest: &Path, branch: Option<&str>) -> Result<()> {
     ensure_git()?;
-    let status = Command::new("git")
-        .arg("clone")
+    let mut cmd = Command::new("git");
+    cmd.arg("clone");
+    if let Some(b) = branch {
+        cmd.args(["--branch", b]);
+    }
+    let status = cmd
         .arg(url)
         .arg(dest)
         .status()
@@ -47,85 +52,447 @@ pub fn clone(url: &str, dest: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn pull(repo_path: &Path) -> Result<()> {
+/// Start a repository in `dir`.
+pub fn init(dir: &Path) -> Result<()> {
     ensure_git()?;
     let status = Command::new("git")
-        .arg("pull")
+        .args(["init", "--quiet"])
+        .current_dir(dir)
+        .status()
+        .context("failed to spawn git init")?;
+    if !status.success() {
+        anyhow::bail!("git init failed");
+    }
+    Ok(())
+}
+
+/// Point `origin` at `url`.
+pub fn add_remote(repo_path: &Path, url: &str) -> Result<()> {
+    ensure_git()?;
+    let out = Command::new("git")
+        .args(["remote", "add", "origin", url])
+        .current_dir(repo_path)
+        .output()
+        .context("failed to spawn git remote")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "cannot add remote '{}': {}",
+            url,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Pull with a merge (never a rebase) so conflicts can be resolved per file.
+/// Returns the files git left conflicted, empty when the pull went through.
+pub fn pull(repo_path: &Path) -> Result<Vec<String>> {
+    ensure_git()?;
+    let status = Command::new("git")
+        .args(["pull", "--no-rebase", "--ff", "--no-edit"])
         .current_dir(repo_path)
         .status()
         .context("failed to spawn git pull")?;
-    if !status.success() {
+    if status.success() {
+        return Ok(Vec::new());
+    }
+    let conflicted = unmerged_files(repo_path)?;
+    if conflicted.is_empty() {
         anyhow::bail!("git pull failed");
     }
+    Ok(conflicted)
+}
+
+/// Repo-relative paths with unresolved merge conflicts.
+pub fn unmerged_files(repo_path: &Path) -> Result<Vec<String>> {
+    let out = Command::new("git")
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .current_dir(repo_path)
+        .output()
+        .context("failed to run git diff")?;
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Undo an unfinished merge, restoring the files to what they were before the pull.
+pub fn merge_abort(repo_path: &Path) -> Result<()> {
+    let status = Command::new("git")
+        .args(["merge", "--abort"])
+        .current_dir(repo_path)
+        .status()
+        .context("failed to spawn git merge --abort")?;
+    if !status.success() {
+        anyhow::bail!("git merge --abort failed");
+    }
     Ok(())
 }
 
-pub fn commit_all_and_push(repo_path: &Path) -> Result<()> {
+/// Mark `files` resolved and conclude the merge with git's default message.
+pub fn commit_merge(repo_path: &Path, files: &[String]) -> Result<()> {
+    let add_status = Command::new("git")
+        .args(["add", "--"])
+        .args(files)
+        .current_dir(repo_path)
+        .status()
+        .context("failed to spawn git add")?;
+    if !add_status.success() {
+        anyhow::bail!("git add failed");
+    }
+    let out = Command::new("git")
+        .args(["commit", "--no-edit"])
+        .current_dir(repo_path)
+        .output()
+        .context("failed to spawn git commit")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git commit failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Changed paths under `pathspecs` (everything when empty) as
+/// (two-letter porcelain status, repo-relative path).
+pub fn status(repo_path: &Path, pathspecs: &[String]) -> Result<Vec<(String, String)>> {
+    ensure_git()?;
+    let out = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=all", "--"])
+        .args(pathspecs)
+        .current_dir(repo_path)
+        .output()
+        .context("failed to run git status")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|l| l.len() > 3)
+        .map(|l| {
+            let path = &l[3..];
+            // Renames are reported as "old -> new"
+            let path = path.rsplit(" -> ").next().unwrap_or(path);
+            (l[..2].to_string(), path.trim_matches('"').to_string())
+        })
+        .collect())
+}
+
+/// Commit `pathspecs` as they are in the working tree (everything when empty)
+/// with `message`, then push. Without a message nothing is committed and only
+/// the push runs.
+pub fn commit_and_push(
+    repo_path: &Path,
+    pathspecs: &[String],
+    message: Option<&str>,
+) -> Result<()> {
     ensure_git()?;
 
     ensure_ralf_gitignore(repo_path)?;
 
+    if let Some(message) = message {
+        if pathspecs.is_empty() {
+            commit_all(repo_path, message)?;
+        } else {
+            commit_only(repo_path, pathspecs, message)?;
+        }
+    }
+
+    // A branch made with 'ralf branch --create' has no upstream until its first push
+    let mut push = Command::new("git");
+    push.arg("push");
+    if upstream(repo_path).is_none() {
+        push.args(["--set-upstream", "origin", "HEAD"]);
+    }
+    let status = push
+        .current_dir(repo_path)
+        .status()
+        .context("failed to spawn git push")?;
+    if !status.success() {
+        anyhow::bail!("git push failed");
+    }
+    Ok(())
+}
+
+/// Files ralf stops tracking on upload, as its .gitignore lists them.
+const UNTRACKED: [&str; 2] = [".ralf_machine", "alf.conf.save"];
+
+/// Stage and commit everything in the working tree.
+fn commit_all(repo_path: &Path, message: &str) -> Result<()> {
     // Untrack files that should be ignored (ignore failures)
     let _ = Command::new("git")
-        .args([
-            "rm",
-            "--cached",
-            "--ignore-unmatch",
-            "--quiet",
-            ".ralf_machine",
-        ])
+        .args(["rm", "--cached", "--ignore-unmatch", "--quiet", "--"])
+        .args(UNTRACKED)
         .current_dir(repo_path)
         .status();
+    run_checked(
+        Command::new("git").args(["add", "-A"]),
+        repo_path,
+        "git add",
+    )?;
+    if staged(repo_path, None)? {
+        run_checked(
+            Command::new("git").args(["commit", "-m", message]),
+            repo_path,
+            "git commit",
+        )?;
+    }
+    Ok(())
+}
+
+/// Commit `pathspecs` alone. They are staged in a scratch index starting at
+/// HEAD, so whatever the user already staged stays out of the commit and
+/// stays staged.
+fn commit_only(repo_path: &Path, pathspecs: &[String], message: &str) -> Result<()> {
+    let out = Command::new("git")
+        .args(["rev-parse", "--git-path", "ralf-upload.index"])
+        .current_dir(repo_path)
+        .output()
+        .context("failed to run git rev-parse")?;
+    let index = repo_path.join(String::from_utf8_lossy(&out.stdout).trim());
+    let git = || {
+        let mut c = Command::new("git");
+        c.env("GIT_INDEX_FILE", &index);
+        c
+    };
+    let result = (|| -> Result<()> {
+        let base = if verify_rev(repo_path, "HEAD").is_ok() {
+            "HEAD"
+        } else {
+            "--empty"
+        };
+        run_checked(git().args(["read-tree", base]), repo_path, "git read-tree")?;
+        let _ = git()
+            .args(["rm", "--cached", "--ignore-unmatch", "--quiet", "--"])
+            .args(UNTRACKED)
+            .current_dir(repo_path)
+            .status();
+        run_checked(
+            git().args(["add", "-A", "--"]).args(pathspecs),
+            repo_path,
+            "git add",
+        )?;
+        if staged(repo_path, Some(&index))? {
+            run_checked(
+                git().args(["commit", "-m", message]),
+                repo_path,
+                "git commit",
+            )?;
+        }
+        Ok(())
+    })();
+    let _ = fs::remove_file(&index);
+    result?;
+    // Bring the real index up to the new HEAD for the committed paths only
     let _ = Command::new("git")
-        .args([
-            "rm",
-            "--cached",
-            "--ignore-unmatch",
-            "--quiet",
-            "alf.conf.save",
-        ])
+        .args(["reset", "-q", "--"])
+        .args(pathspecs)
+        .args(UNTRACKED)
         .current_dir(repo_path)
         .status();
+    Ok(())
+}
 
-    // Stage all changes (new, modified, deleted)
-    let add_status = Command::new("git")
-        .args(["add", "-A"])
+/// Whether the index (or the one at `index`) differs from HEAD.
+fn staged(repo_path: &Path, index: Option<&Path>) -> Result<bool> {
+    let mut c = Command::new("git");
+    if let Some(index) = index {
+        c.env("GIT_INDEX_FILE", index);
+    }
+    Ok(!c
+        .args(["diff", "--cached", "--quiet"])
         .current_dir(repo_path)
         .status()
-        .context("failed to spawn git add")?;
-    if !add_status.success() {
-        anyhow::bail!("git add failed");
+        .context("failed to run git diff")?
+        .success())
+}
+
+fn run_checked(cmd: &mut Command, repo_path: &Path, what: &str) -> Result<()> {
+    let out = cmd
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("failed to spawn {}", what))?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
     }
+    Ok(())
+}
 
-    // Commit whatever is in the index. If there's nothing to commit, continue silently.
-    let commit_out = Command::new("git")
-        .args(["commit", "-m", "automatic push"])
+/// URL of the `origin` remote, None when the directory has none.
+pub fn remote_url(repo_path: &Path) -> Option<String> {
+    let out = Command::new("git")
+        .args(["config", "--get", "remote.origin.url"])
         .current_dir(repo_path)
         .output()
-        .context("failed to spawn git commit")?;
-    if !commit_out.status.success() {
-        let status_out = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(repo_path)
-            .output()
-            .context("failed to run git status")?;
-        let dirty = !String::from_utf8_lossy(&status_out.stdout)
-            .trim()
-            .is_empty();
-        if dirty {
-            let stderr = String::from_utf8_lossy(&commit_out.stderr);
-            anyhow::bail!("git commit failed: {}", stderr.trim());
-        }
-        // else: nothing to commit, proceed to push
+        .ok()?;
+    let url = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !url.is_empty()).then_some(url)
+}
+
+/// Whether git tracks `path` (a file or directory) in the repo.
+pub fn tracked(repo_path: &Path, path: &str) -> bool {
+    Command::new("git")
+        .args(["ls-files", "--", path])
+        .current_dir(repo_path)
+        .output()
+        .map(|o| o.status.success() && !o.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// `<short hash> <date> <subject>` of the last commit touching `file`, if any.
+pub fn last_commit(repo_path: &Path, file: &Path) -> Option<String> {
+    let out = Command::new("git")
+        .args(["log", "-1", "--format=%h %cs %s", "--"])
+        .arg(file)
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !line.is_empty()).then_some(line)
+}
+
+/// Short hash of HEAD, None outside a git checkout.
+pub fn head_commit(repo_path: &Path) -> Option<String> {
+    let out = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+    let hash = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !hash.is_empty()).then_some(hash)
+}
+
+/// Check that `rev` names a commit in the repo.
+pub fn verify_rev(repo_path: &Path, rev: &str) -> Result<()> {
+    ensure_git()?;
+    let out = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", rev))
+        .current_dir(repo_path)
+        .output()
+        .context("failed to spawn git rev-parse")?;
+    if !out.status.success() {
+        anyhow::bail!("unknown revision '{}'", rev);
     }
+    Ok(())
+}
+
+/// Contents of `file` (relative to the repo) at `rev`, or None when it did not exist there.
+pub fn show_file(repo_path: &Path, rev: &str, file: &Path) -> Result<Option<String>> {
+    let spec = format!("{}:{}", rev, file.to_string_lossy().replace('\\', "/"));
+    let out = Command::new("git")
+        .arg("show")
+        .arg(spec)
+        .current_dir(repo_path)
+        .output()
+        .context("failed to spawn git show")?;
+    Ok(out
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned()))
+}
+
+/// Name of the checked-out branch, None when HEAD is detached.
+pub fn current_branch(repo_path: &Path) -> Option<String> {
+    let out = Command::new("git")
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !name.is_empty()).then_some(name)
+}
 
+/// Upstream of the current branch with (ahead, behind) commit counts as of the
+/// last fetch, or None when no upstream is configured.
+pub fn upstream(repo_path: &Path) -> Option<(String, usize, usize)> {
+    let out = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || name.is_empty() {
+        return None;
+    }
+    let out = Command::new("git")
+        .args(["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+    let counts = String::from_utf8_lossy(&out.stdout);
+    let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+    Some((name, counts.next()?, counts.next()?))
+}
+
+pub fn fetch(repo_path: &Path) -> Result<()> {
+    ensure_git()?;
     let status = Command::new("git")
-        .arg("push")
+        .args(["fetch", "--quiet"])
         .current_dir(repo_path)
         .status()
-        .context("failed to spawn git push")?;
+        .context("failed to spawn git fetch")?;
     if !status.success() {
-        anyhow::bail!("git push failed");
+        anyhow::bail!("git fetch failed");
+    }
+    Ok(())
+}
+
+/// Check out `branch`, creating it from the current commit when `create` is set.
+/// A branch that only exists on the remote is checked out tracking it.
+pub fn switch_branch(repo_path: &Path, branch: &str, create: bool) -> Result<()> {
+    ensure_git()?;
+    let mut cmd = Command::new("git");
+    cmd.arg("switch");
+    if create {
+        cmd.arg("--create");
+    }
+    let out = cmd
+        .arg(branch)
+        .current_dir(repo_path)
+        .output()
+        .context("failed to spawn git switch")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "cannot switch to branch '{}': {}",
+            branch,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
     }
     Ok(())
 }
+
+/// Local branches plus those only on origin, by name.
+pub fn branches(repo_path: &Path) -> Result<Vec<String>> {
+    ensure_git()?;
+    let out = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/heads",
+            "refs/remotes/origin",
+        ])
+        .current_dir(repo_path)
+        .output()
+        .context("failed to run git for-each-ref")?;
+    let mut names: Vec<String> = Vec::new();
+    for r in String::from_utf8_lossy(&out.stdout).lines() {
+        let name = r
+            .strip_prefix("refs/heads/")
+            .or_else(|| r.strip_prefix("refs/remotes/origin/"))
+            .unwrap_or(r);
+        if name != "HEAD" && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
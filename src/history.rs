@@ -0,0 +1,139 @@
+// Generations kept by `ralf save` under <config dir>/history/<generation>/:
+// the unified sh and fish files, the compat aliases file and meta.json
+// recording where they came from. Newest first everywhere in this module.
+
+use crate::paths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many generations a save keeps around.
+pub const KEEP: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Meta {
+    pub generation: String,
+    /// Seconds since the epoch
+    pub saved: u64,
+    /// Short commit of the config repo, when it is a git checkout
+    pub commit: Option<String>,
+    pub machine: String,
+    /// Where the compat copy lives (the aliases file of the shell that saved)
+    pub compat_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub dir: PathBuf,
+    pub meta: Meta,
+}
+
+impl Entry {
+    pub fn sh(&self) -> String {
+        fs::read_to_string(self.dir.join("aliases.sh")).unwrap_or_default()
+    }
+
+    pub fn fish(&self) -> String {
+        fs::read_to_string(self.dir.join("aliases.fish")).unwrap_or_default()
+    }
+
+    pub fn compat(&self) -> String {
+        fs::read_to_string(self.dir.join("compat")).unwrap_or_default()
+    }
+}
+
+pub fn history_dir() -> PathBuf {
+    paths::config_dir().join("history")
+}
+
+/// Kept generations, newest first. Unreadable entries are skipped.
+pub fn entries() -> Vec<Entry> {
+    let Ok(dirs) = fs::read_dir(history_dir()) else {
+        return Vec::new();
+    };
+    let mut out: Vec<Entry> = dirs
+        .flatten()
+        .filter_map(|d| {
+            let dir = d.path();
+            let text = fs::read_to_string(dir.join("meta.json")).ok()?;
+            let meta = serde_json::from_str(&text).ok()?;
+            Some(Entry { dir, meta })
+        })
+        .collect();
+    out.sort_by(|a, b| (b.meta.saved, &b.meta.generation).cmp(&(a.meta.saved, &a.meta.generation)));
+    out
+}
+
+/// Generated text without its generation stamp, to spot saves that changed nothing.
+pub fn unstamped(text: &str) -> String {
+    text.lines()
+        .filter(|l| !l.contains("RALF_GENERATION"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keep a save's output, unless it matches the newest generation, then drop
+/// generations beyond `KEEP`.
+pub fn record(meta: Meta, sh: &str, fish: &str, compat: &str) -> Result<()> {
+    let existing = entries();
+    if let Some(latest) = existing.first() {
+        if unstamped(&latest.sh()) == unstamped(sh)
+            && unstamped(&latest.compat()) == unstamped(compat)
+            && latest.meta.compat_path == meta.compat_path
+        {
+            return Ok(());
+        }
+    }
+    let dir = history_dir().join(&meta.generation);
+    fs::create_dir_all(&dir).with_context(|| format!("cannot create {}", dir.display()))?;
+    fs::write(dir.join("aliases.sh"), sh)?;
+    fs::write(dir.join("aliases.fish"), fish)?;
+    fs::write(dir.join("compat"), compat)?;
+    fs::write(dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+    for old in entries().iter().skip(KEEP) {
+        let _ = fs::remove_dir_all(&old.dir);
+    }
+    Ok(())
+}
+
+/// Put a kept generation back in place of the generated files.
+pub fn restore(entry: &Entry) -> Result<Vec<PathBuf>> {
+    let cfg_dir = paths::config_dir();
+    // The compat copy is written last by a save, so it wins when it shares a path
+    let targets = [
+        (entry.meta.compat_path.clone(), entry.compat()),
+        (cfg_dir.join("aliases.sh"), entry.sh()),
+        (cfg_dir.join("aliases.fish"), entry.fish()),
+    ];
+    let mut written = Vec::new();
+    for (path, text) in targets {
+        if written.contains(&path) {
+            continue;
+        }
+        write_atomic(&path, &text)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn write_atomic(path: &Path, text: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("ralf-new");
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// "just now", "5 min ago", "3 h ago", "2 days ago"
+pub fn ago(saved: u64, now: u64) -> String {
+    let secs = now.saturating_sub(saved);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86_399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86_400),
+    }
+}
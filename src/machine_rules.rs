@@ -0,0 +1,237 @@
+// `machines/rules`: map facts about the host to a machine id.
+//
+// One rule per line, `<id>: <condition> [<condition>...]`; the first rule
+// whose conditions all hold wins. Conditions are `key=glob` with keys
+// `hostname`, `machine-id`, `os` and `user`, or `env:NAME=glob` (`env:NAME`
+// alone only checks that the variable is set).
+//
+// `@<group>: <condition>...` lines put the machine in a group instead, and
+// every one that holds applies. They may also test the machine id picked by
+// the other rules with `machine=glob`.
+
+use std::process::Command;
+
+/// What the rules can look at.
+#[derive(Clone, Debug, Default)]
+pub struct Facts {
+    /// Full and short host name
+    pub hostnames: Vec<String>,
+    pub machine_id: Option<String>,
+    /// `ID` and `ID_LIKE` from /etc/os-release, plus the Rust OS name (linux, macos, ...)
+    pub os: Vec<String>,
+    pub user: Option<String>,
+    /// Machine id, known once picked; only group rules look at it
+    pub machine: Option<String>,
+    /// Environment variables, empty for facts about another machine
+    pub env: Vec<(String, String)>,
+}
+
+impl Facts {
+    pub fn gather() -> Facts {
+        let mut hostnames = Vec::new();
+        if let Ok(h) = std::env::var("HOSTNAME") {
+            hostnames.push(h.trim().to_string());
+        } else if let Ok(out) = Command::new("hostname").output() {
+            hostnames.push(String::from_utf8_lossy(&out.stdout).trim().to_string());
+        }
+        if let Some(short) = hostnames.first().and_then(|h| h.split('.').next()) {
+            let short = short.to_string();
+            if !hostnames.contains(&short) {
+                hostnames.push(short);
+            }
+        }
+        hostnames.retain(|h| !h.is_empty());
+
+        let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .iter()
+            .filter_map(|f| std::fs::read_to_string(f).ok())
+            .map(|s| s.trim().to_string())
+            .find(|s| !s.is_empty());
+
+        let mut os = Vec::new();
+        if let Ok(text) = std::fs::read_to_string("/etc/os-release") {
+            for line in text.lines() {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                if key == "ID" || key == "ID_LIKE" {
+                    let value = value.trim_matches('"');
+                    os.extend(value.split_whitespace().map(str::to_string));
+                }
+            }
+        }
+        os.push(std::env::consts::OS.to_string());
+
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("LOGNAME"))
+            .ok()
+            .filter(|u| !u.is_empty());
+
+        Facts {
+            hostnames,
+            machine_id,
+            os,
+            user,
+            machine: None,
+            env: std::env::vars().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    Hostname(String),
+    MachineId(String),
+    Os(String),
+    User(String),
+    Machine(String),
+    Env(String, Option<String>),
+}
+
+impl Condition {
+    fn parse(s: &str) -> Result<Condition, String> {
+        if let Some(rest) = s.strip_prefix("env:") {
+            return Ok(match rest.split_once('=') {
+                Some((name, glob)) => Condition::Env(name.to_string(), Some(glob.to_string())),
+                None => Condition::Env(rest.to_string(), None),
+            });
+        }
+        let Some((key, glob)) = s.split_once('=') else {
+            return Err(format!("expected key=pattern, got '{}'", s));
+        };
+        let glob = glob.to_string();
+        match key {
+            "hostname" | "host" => Ok(Condition::Hostname(glob)),
+            "machine-id" => Ok(Condition::MachineId(glob)),
+            "os" | "distro" => Ok(Condition::Os(glob)),
+            "user" => Ok(Condition::User(glob)),
+            "machine" => Ok(Condition::Machine(glob)),
+            _ => Err(format!(
+                "unknown condition '{}' (expected hostname, machine-id, os, user, machine or env:NAME)",
+                key
+            )),
+        }
+    }
+
+    /// None when the condition holds, otherwise why it does not.
+    fn mismatch(&self, facts: &Facts) -> Option<String> {
+        let check = |what: &str, glob: &str, values: &[String]| {
+            if values.iter().any(|v| glob_match(glob, v)) {
+                None
+            } else if values.is_empty() {
+                Some(format!("{} is unknown", what))
+            } else {
+                Some(format!("{} is {}", what, values.join("/")))
+            }
+        };
+        match self {
+            Condition::Hostname(g) => check("hostname", g, &facts.hostnames),
+            Condition::MachineId(g) => {
+                check("machine-id", g, &Vec::from_iter(facts.machine_id.clone()))
+            }
+            Condition::Os(g) => check("os", g, &facts.os),
+            Condition::User(g) => check("user", g, &Vec::from_iter(facts.user.clone())),
+            Condition::Machine(g) => check("machine", g, &Vec::from_iter(facts.machine.clone())),
+            Condition::Env(name, glob) => {
+                let value = facts.env.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+                match (value, glob) {
+                    (None, _) => Some(format!("${} is not set", name)),
+                    (Some(_), None) => None,
+                    (Some(v), Some(g)) => check(&format!("${}", name), g, std::slice::from_ref(v)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// 1-based line in the rules file
+    pub line: usize,
+    /// Machine id, or group name for an `@<group>:` rule
+    pub id: String,
+    pub group: bool,
+    pub text: String,
+    pub conditions: Vec<Condition>,
+}
+
+impl Rule {
+    /// None when every condition holds, otherwise the first that does not.
+    pub fn mismatch(&self, facts: &Facts) -> Option<String> {
+        self.conditions.iter().find_map(|c| c.mismatch(facts))
+    }
+}
+
+/// Rules in file order, plus (line, message) for lines that could not be read.
+pub fn parse_rules(text: &str) -> (Vec<Rule>, Vec<(usize, String)>) {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((id, conds)) = line.split_once(':') else {
+            errors.push((i + 1, "expected '<id>: <condition>...'".to_string()));
+            continue;
+        };
+        let id = id.trim();
+        let (id, group) = match id.strip_prefix('@') {
+            Some(name) => (name, true),
+            None => (id, false),
+        };
+        if !crate::config_merge::valid_machine_id(id) {
+            let what = if group { "group name" } else { "machine id" };
+            errors.push((i + 1, format!("invalid {} '{}'", what, id)));
+            continue;
+        }
+        let conds = conds.trim();
+        if !group && conds.split_whitespace().any(|c| c.starts_with("machine=")) {
+            errors.push((
+                i + 1,
+                "'machine=' only works in '@<group>:' rules".to_string(),
+            ));
+            continue;
+        }
+        let parsed: Result<Vec<Condition>, String> =
+            conds.split_whitespace().map(Condition::parse).collect();
+        match parsed {
+            Ok(conditions) if conditions.is_empty() => {
+                errors.push((i + 1, format!("rule for '{}' has no conditions", id)))
+            }
+            Ok(conditions) => rules.push(Rule {
+                line: i + 1,
+                id: id.to_string(),
+                group,
+                text: conds.to_string(),
+                conditions,
+            }),
+            Err(e) => errors.push((i + 1, e)),
+        }
+    }
+    (rules, errors)
+}
+
+/// Shell-style glob with `*` and `?`, case-insensitive.
+pub fn glob_match(glob: &str, s: &str) -> bool {
+    let g: Vec<char> = glob.to_lowercase().chars().collect();
+    let t: Vec<char> = s.to_lowercase().chars().collect();
+    let (mut gi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if gi < g.len() && (g[gi] == '?' || g[gi] == t[ti]) {
+            gi += 1;
+            ti += 1;
+        } else if gi < g.len() && g[gi] == '*' {
+            star = Some((gi, ti));
+            gi += 1;
+        } else if let Some((sg, st)) = star {
+            gi = sg + 1;
+            ti = st + 1;
+            star = Some((sg, st + 1));
+        } else {
+            return false;
+        }
+    }
+    g[gi..].iter().all(|c| *c == '*')
+}
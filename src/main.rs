@@ -7,20 +7,40 @@ mod config_merge;
 mod domain;
 mod generator;
 mod gitwrap;
+mod history;
+mod machine_rules;
 mod paths;
+mod settings;
+mod shadow;
+mod sync;
 mod tui;
 
+mod cmd_add;
+mod cmd_branch;
+mod cmd_check;
 mod cmd_clean;
+mod cmd_config;
 mod cmd_connect;
+mod cmd_diff;
 mod cmd_download;
 mod cmd_edit;
+mod cmd_expand;
 mod cmd_generate;
 mod cmd_help;
+mod cmd_history;
 mod cmd_info;
+mod cmd_list;
 mod cmd_machine;
 mod cmd_menu;
+mod cmd_new;
 mod cmd_reset;
+mod cmd_rm;
+mod cmd_run;
 mod cmd_save;
+mod cmd_search;
+mod cmd_set;
+mod cmd_stats;
+mod cmd_status;
 mod cmd_upgrade;
 mod cmd_upload;
 mod cmd_which;
@@ -29,18 +49,38 @@ fn main() -> Result<()> {
     let cli = cli::Cli::parse();
     match cli.command {
         Some(cli::Commands::Connect(args)) => cmd_connect::run(args)?,
-        Some(cli::Commands::Download) => cmd_download::run()?,
-        Some(cli::Commands::Upload) => cmd_upload::run()?,
+        Some(cli::Commands::New(args)) => cmd_new::run(args)?,
+        Some(cli::Commands::Download(args)) => cmd_download::run(args)?,
+        Some(cli::Commands::Upload(args)) => cmd_upload::run(args)?,
+        Some(cli::Commands::Status(args)) => cmd_status::run(args)?,
+        Some(cli::Commands::Branch(args)) => cmd_branch::run(args)?,
+        Some(cli::Commands::History { json }) => cmd_history::run(json)?,
+        Some(cli::Commands::Config(args)) => cmd_config::run(args)?,
+        Some(cli::Commands::Rollback { n }) => cmd_history::rollback(n)?,
         Some(cli::Commands::Generate) => cmd_generate::run()?,
         Some(cli::Commands::Save) => cmd_save::run()?,
         Some(cli::Commands::Edit { what }) => cmd_edit::run(what)?,
-        Some(cli::Commands::Which { code, subcode }) => cmd_which::run(code, subcode)?,
+        Some(cli::Commands::Which {
+            code,
+            subcode,
+            trace,
+        }) => cmd_which::run(code, subcode, trace)?,
         Some(cli::Commands::Help { topic }) => cmd_help::run(topic)?,
         Some(cli::Commands::Clean { purge }) => cmd_clean::run(purge)?,
         Some(cli::Commands::Reset) => cmd_reset::run()?,
-        Some(cli::Commands::Upgrade) => cmd_upgrade::run()?,
-        Some(cli::Commands::Machine { name }) => cmd_machine::run(name)?,
+        Some(cli::Commands::Upgrade(args)) => cmd_upgrade::run(args)?,
+        Some(cli::Commands::Run { words }) => cmd_run::run(words)?,
+        Some(cli::Commands::Expand { shell, words }) => cmd_expand::run(words, shell)?,
+        Some(cli::Commands::Machine(args)) => cmd_machine::run(args)?,
         Some(cli::Commands::Info) => cmd_info::run()?,
+        Some(cli::Commands::Stats(args)) => cmd_stats::run(args)?,
+        Some(cli::Commands::Check) => cmd_check::run()?,
+        Some(cli::Commands::List(args)) => cmd_list::run(args)?,
+        Some(cli::Commands::Search(args)) => cmd_search::run(args)?,
+        Some(cli::Commands::Diff(args)) => cmd_diff::run(args)?,
+        Some(cli::Commands::Add(args)) => cmd_add::run(args)?,
+        Some(cli::Commands::Set(args)) => cmd_set::run(args)?,
+        Some(cli::Commands::Rm(args)) => cmd_rm::run(args)?,
         None => cmd_menu::run()?,
     }
     Ok(())
@@ -37,11 +37,25 @@ pub fn env_rc_file() -> PathBuf {
     }
 }
 
+/// Whether the environment names an rc file, which then holds the repo.
+pub fn rc_env_set() -> bool {
+    ["RALF_RC_FILE", "ralf_RC_FILE", "ALF_RC_FILE"]
+        .iter()
+        .any(|v| std::env::var(v).is_ok())
+}
+
+/// Whether the rc file named by the environment records a repo. Only then
+/// does it take precedence over config.toml; a name alone, such as one left
+/// exported by an older aliases file, does not.
+pub fn rc_env_active() -> bool {
+    rc_env_set()
+        && fs::read_to_string(env_rc_file())
+            .map(|c| !rc_repo(&c).is_empty())
+            .unwrap_or(false)
+}
+
 pub fn env_aliases_file() -> PathBuf {
-    if let Ok(s) = std::env::var("RALF_ALIASES_FILE")
-        .or_else(|_| std::env::var("ralf_ALIASES_FILE"))
-        .or_else(|_| std::env::var("ALF_ALIASES_FILE"))
-    {
+    if let Some(s) = crate::settings::value("aliases_file") {
         PathBuf::from(shellexpand::tilde(&s).into_owned())
     } else {
         let is_fish = std::env::var("FISH_VERSION").is_ok()
@@ -62,22 +76,22 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
     let rc_file = env_rc_file();
     let aliases_file = env_aliases_file();
     let cwd = std::env::current_dir()?;
-    let rc_env_set = std::env::var("RALF_RC_FILE").is_ok()
-        || std::env::var("ralf_RC_FILE").is_ok()
-        || std::env::var("ALF_RC_FILE").is_ok();
+    let rc_active = rc_env_active();
 
-    // Bash parity:
-    // repo_path defaults to "$PWD/ralf-conf"
-    // config_file defaults to "ralf.conf" (in current dir)
+    // Where the config is found, first match wins:
+    // 1. the rc file named by RALF_RC_FILE (or ralf_RC_FILE / ALF_RC_FILE),
+    //    when it records a repo
+    // 2. the current directory or its nearest parent holding ralf.conf or
+    //    alf.conf, directly or in a ralf-conf / alf-conf subdirectory
+    // 3. the repo setting in config.toml, else ~/.ralfrc (or ~/.alfrc)
+    // 4. the default clone location, $XDG_DATA_HOME/ralf/repo
     let mut repo_path = cwd.join("ralf-conf");
     let mut config_file = cwd.join("ralf.conf");
 
-    if rc_env_set && rc_file.exists() {
-        let content = fs::read_to_string(&rc_file)?.trim().to_string();
-        if !content.is_empty() {
-            repo_path = PathBuf::from(content);
-            config_file = repo_path.join("ralf.conf");
-        }
+    let rc_content = fs::read_to_string(&rc_file).unwrap_or_default();
+    if rc_active {
+        repo_path = PathBuf::from(rc_repo(&rc_content));
+        config_file = repo_path.join("ralf.conf");
     }
 
     // Legacy detection and migration to ralf
@@ -98,6 +112,46 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
         }
     }
 
+    // Nothing usable from an rc env: walk up, then the settings, then the default
+    let mut from_rc = rc_active;
+    // Legacy names are migrated in the repo picked from the rc, the current
+    // directory or the settings; a parent found on the walk up is left alone
+    let mut migrate = true;
+    if !config_file.exists() && !repo_path.join("alf.conf").exists() {
+        from_rc = false;
+        if let Some(repo) = cwd.ancestors().find_map(find_in) {
+            migrate = repo == cwd || repo.parent() == Some(cwd.as_path());
+            repo_path = repo;
+            config_file = repo_path.join("ralf.conf");
+            if !migrate && !config_file.exists() {
+                config_file = repo_path.join("alf.conf");
+            }
+        } else {
+            let setting = if rc_active {
+                None
+            } else {
+                crate::settings::value("repo")
+            };
+            repo_path = setting.map(PathBuf::from).unwrap_or_else(default_repo);
+            config_file = repo_path.join("ralf.conf");
+        }
+    }
+    if !migrate {
+        return found(rc_file, aliases_file, repo_path, config_file);
+    }
+
+    // An alf-conf subdirectory of the current one becomes ralf-conf
+    if repo_path.file_name().is_some_and(|n| n == "alf-conf")
+        && repo_path.parent() == Some(cwd.as_path())
+    {
+        let new_repo = repo_path.with_file_name("ralf-conf");
+        if !new_repo.exists() && fs::rename(&repo_path, &new_repo).is_ok() {
+            repo_path = new_repo;
+            config_file = repo_path.join("ralf.conf");
+            migrated = true;
+        }
+    }
+
     // If config file missing, look for alf.conf and migrate it to ralf.conf
     if !config_file.exists() {
         let alf_in_repo = repo_path.join("alf.conf");
@@ -108,52 +162,6 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
             }
             config_file = new_cf;
             migrated = true;
-        } else {
-            // Search common locations in CWD and typical repo folders
-            let ralf_cwd = cwd.join("ralf.conf");
-            let alf_cwd = cwd.join("alf.conf");
-            if ralf_cwd.exists() {
-                config_file = ralf_cwd;
-                repo_path = cwd.clone();
-            } else if alf_cwd.exists() {
-                let new_cf = cwd.join("ralf.conf");
-                if !new_cf.exists() {
-                    let _ = fs::rename(&alf_cwd, &new_cf);
-                }
-                config_file = new_cf;
-                repo_path = cwd.clone();
-                migrated = true;
-            } else {
-                for d in ["ralf-conf", "alf-conf"] {
-                    let dir = cwd.join(d);
-                    let cf_ralf = dir.join("ralf.conf");
-                    let cf_alf = dir.join("alf.conf");
-                    if cf_ralf.exists() {
-                        repo_path = dir.clone();
-                        config_file = cf_ralf;
-                        break;
-                    } else if cf_alf.exists() {
-                        let new_cf = dir.join("ralf.conf");
-                        if !new_cf.exists() {
-                            let _ = fs::rename(&cf_alf, &new_cf);
-                        }
-                        if d == "alf-conf" {
-                            let new_dir = cwd.join("ralf-conf");
-                            if !new_dir.exists() {
-                                let _ = fs::rename(&dir, &new_dir);
-                                repo_path = new_dir;
-                            } else {
-                                repo_path = dir;
-                            }
-                        } else {
-                            repo_path = dir;
-                        }
-                        config_file = new_cf;
-                        migrated = true;
-                        break;
-                    }
-                }
-            }
         }
     }
 
@@ -171,8 +179,9 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
         migrated = true;
     }
 
-    // If rc file is .alfrc, migrate to .ralfrc (if not already present)
-    if rc_env_set && rc_file.file_name().map(|n| n == ".alfrc").unwrap_or(false) {
+    // If rc file is .alfrc, migrate to .ralfrc (if not already present). The
+    // rc only ever records the repo it named, never one found some other way
+    if from_rc && rc_file.file_name().map(|n| n == ".alfrc").unwrap_or(false) {
         let new_rc = rc_file.with_file_name(".ralfrc");
         let rc_file = if !new_rc.exists() && fs::rename(&rc_file, &new_rc).is_ok() {
             new_rc
@@ -183,7 +192,7 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
         if let Some(parent) = rc_file.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&rc_file, format!("{}\n", repo_path.to_string_lossy()))?;
+        write_rc(&rc_file, &repo_path)?;
         // Rebuild Paths locals to continue with the migrated rc path
         let aliases_file = aliases_file;
         return Ok(Paths {
@@ -195,13 +204,23 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
     }
 
     // If anything migrated, ensure rc file content points to the new repo_path
-    if migrated && rc_env_set && rc_file.exists() {
+    if migrated && from_rc && rc_file.exists() {
         if let Some(parent) = rc_file.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&rc_file, format!("{}\n", repo_path.to_string_lossy()))?;
+        write_rc(&rc_file, &repo_path)?;
     }
 
+    found(rc_file, aliases_file, repo_path, config_file)
+}
+
+/// The paths picked, or exit when the config file is not there.
+fn found(
+    rc_file: PathBuf,
+    aliases_file: PathBuf,
+    repo_path: PathBuf,
+    config_file: PathBuf,
+) -> anyhow::Result<Paths> {
     if !config_file.exists() {
         // Match bash-src/lib/find_config.sh exact text
         print!("ERROR: Cannot find config file\n\n");
@@ -219,6 +238,48 @@ pub fn find_config_or_exit() -> anyhow::Result<Paths> {
     })
 }
 
+/// Look for a config in `dir`: ralf.conf or alf.conf there, or in a ralf-conf
+/// or alf-conf subdirectory. Returns the repo; legacy names are left for the
+/// caller to migrate once it settles on one.
+fn find_in(dir: &Path) -> Option<PathBuf> {
+    let has_config = |d: &Path| d.join("ralf.conf").exists() || d.join("alf.conf").exists();
+    if has_config(dir) {
+        return Some(dir.to_path_buf());
+    }
+    ["ralf-conf", "alf-conf"]
+        .iter()
+        .map(|d| dir.join(d))
+        .find(|sub| has_config(sub))
+}
+
+/// `$XDG_DATA_HOME/ralf`, or `~/.local/share/ralf`.
+pub fn data_dir() -> PathBuf {
+    if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
+        PathBuf::from(shellexpand::tilde(&xdg).into_owned()).join("ralf")
+    } else {
+        home_dir()
+            .unwrap()
+            .join(".local")
+            .join("share")
+            .join("ralf")
+    }
+}
+
+/// Where 'ralf connect' clones to unless told otherwise.
+pub fn default_repo() -> PathBuf {
+    data_dir().join("repo")
+}
+
+/// Repo path from rc content. The rc file holds that one line only, as
+/// other ralf and alf installs sharing the home directory read it whole.
+pub fn rc_repo(content: &str) -> &str {
+    content.lines().next().unwrap_or("").trim()
+}
+
+pub fn write_rc(rc_file: &Path, repo: &Path) -> std::io::Result<()> {
+    fs::write(rc_file, format!("{}\n", repo.to_string_lossy()))
+}
+
 pub fn friendly(path: &Path) -> String {
     let p = path.to_string_lossy().into_owned();
     if let Some(home) = home_dir() {
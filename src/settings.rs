@@ -0,0 +1,385 @@
+// User settings in <config dir>/config.toml. Only flat `key = "value"` pairs
+// are used, so the file is read and written here instead of through a TOML
+// library, keeping comments and unknown lines as they are.
+//
+// A setting resolves as: its environment variables, then config.toml, then the
+// place it was kept before this file existed (.ralfrc, ~/.ralf_theme, the
+// branch file, $VISUAL/$EDITOR).
+
+use crate::paths;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct Setting {
+    pub key: &'static str,
+    /// Variables overriding the file, first set one wins
+    pub env: &'static [&'static str],
+    pub about: &'static str,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "repo",
+        env: &[],
+        about: "Config repository (written by 'ralf connect')",
+    },
+    Setting {
+        key: "sync",
+        env: &[],
+        about: "How the repo syncs: git, dir or copy:<path>",
+    },
+    Setting {
+        key: "branch",
+        env: &[],
+        about: "Branch ralf follows",
+    },
+    Setting {
+        key: "machine",
+        env: &["RALF_MACHINE", "ALF_MACHINE", "ralf_MACHINE", "alf_MACHINE"],
+        about: "Machine id when the repo has no marker",
+    },
+    Setting {
+        key: "aliases_file",
+        env: &["RALF_ALIASES_FILE", "ralf_ALIASES_FILE", "ALF_ALIASES_FILE"],
+        about: "Where the aliases file for the login shell is written",
+    },
+    Setting {
+        key: "theme",
+        env: &["RALF_THEME"],
+        about: "TUI theme",
+    },
+    Setting {
+        key: "editor",
+        env: &[],
+        about: "Editor for 'ralf edit' (else $VISUAL, $EDITOR, vi)",
+    },
+    Setting {
+        key: "sudo",
+        env: &[],
+        about: "Escalation tool unless a config sets @sudo",
+    },
+    Setting {
+        key: "root",
+        env: &[],
+        about: "Root policy unless a config sets @root",
+    },
+    Setting {
+        key: "forge",
+        env: &["RALF_FORGE"],
+        about: "Forge for connect shorthands without a prefix",
+    },
+    Setting {
+        key: "upgrade_url",
+        env: &["RALF_UPGRADE_URL"],
+        about: "Release manifest 'ralf upgrade' reads",
+    },
+];
+
+/// Where a value came from.
+pub enum Source {
+    Env(&'static str),
+    File,
+    Legacy(String),
+}
+
+impl Source {
+    pub fn describe(&self) -> String {
+        match self {
+            Source::Env(var) => format!("environment variable {}", var),
+            Source::File => paths::friendly(&path()),
+            Source::Legacy(from) => from.clone(),
+        }
+    }
+}
+
+pub fn path() -> PathBuf {
+    paths::config_dir().join("config.toml")
+}
+
+pub fn find(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+/// `key = "value"` pairs; comments, tables and anything else are skipped.
+pub fn parse(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|l| {
+            let l = l.trim();
+            if l.starts_with('#') || l.starts_with('[') {
+                return None;
+            }
+            let (k, v) = l.split_once('=')?;
+            Some((k.trim().to_string(), unquote(v.trim())?))
+        })
+        .collect()
+}
+
+fn unquote(v: &str) -> Option<String> {
+    let Some(inner) = v.strip_prefix('"') else {
+        // Bare values (numbers, booleans) up to a trailing comment
+        let bare = v.split('#').next().unwrap_or("").trim();
+        return (!bare.is_empty()).then(|| bare.to_string());
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn quote(v: &str) -> String {
+    let escaped = v
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// The value in config.toml only.
+pub fn file_value(key: &str) -> Option<String> {
+    let text = fs::read_to_string(path()).ok()?;
+    parse(&text)
+        .into_iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+}
+
+/// Effective value of a setting and where it came from.
+pub fn lookup(key: &str) -> Option<(String, Source)> {
+    // An rc file named in the environment keeps the repo
+    if key == "repo" && paths::rc_env_active() {
+        return rc_repo().map(|v| (v, Source::Legacy(rc_source())));
+    }
+    if let Some(setting) = find(key) {
+        for var in setting.env {
+            if let Ok(v) = std::env::var(var) {
+                if !v.trim().is_empty() {
+                    return Some((v.trim().to_string(), Source::Env(var)));
+                }
+            }
+        }
+    }
+    if let Some(v) = file_value(key) {
+        return Some((v, Source::File));
+    }
+    legacy(key)
+}
+
+pub fn value(key: &str) -> Option<String> {
+    lookup(key).map(|(v, _)| v)
+}
+
+fn rc_repo() -> Option<String> {
+    let content = fs::read_to_string(paths::env_rc_file()).ok()?;
+    Some(paths::rc_repo(&content).to_string()).filter(|v| !v.is_empty())
+}
+
+fn rc_source() -> String {
+    paths::friendly(&paths::env_rc_file())
+}
+
+fn read_trimmed(file: &Path) -> Option<String> {
+    fs::read_to_string(file)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn legacy(key: &str) -> Option<(String, Source)> {
+    let from_file =
+        |file: PathBuf| read_trimmed(&file).map(|v| (v, Source::Legacy(paths::friendly(&file))));
+    match key {
+        "repo" => rc_repo().map(|v| (v, Source::Legacy(rc_source()))),
+        "branch" => from_file(paths::config_dir().join("branch")),
+        "theme" => from_file(dirs::home_dir()?.join(".ralf_theme")),
+        "editor" => ["VISUAL", "EDITOR"].into_iter().find_map(|var| {
+            let v = std::env::var(var).ok().filter(|v| !v.trim().is_empty())?;
+            Some((v, Source::Env(var)))
+        }),
+        _ => None,
+    }
+}
+
+/// Check a value before it is stored; returns it normalised.
+fn validate(key: &str, value: &str) -> Result<String> {
+    let v = value.trim();
+    if v.is_empty() {
+        bail!("{} cannot be empty (use 'ralf config unset {}')", key, key);
+    }
+    let absolute = |v: &str| -> Result<String> {
+        let p = PathBuf::from(shellexpand::tilde(v).into_owned());
+        Ok(std::env::current_dir()?
+            .join(p)
+            .to_string_lossy()
+            .into_owned())
+    };
+    Ok(match key {
+        "repo" => {
+            let repo = absolute(v)?;
+            if !Path::new(&repo).is_dir() {
+                bail!("no such directory: {}", repo);
+            }
+            repo
+        }
+        "aliases_file" => absolute(v)?,
+        "sync" => match v.strip_prefix("copy:") {
+            Some(source) if !source.is_empty() => format!("copy:{}", absolute(source)?),
+            None if v == "git" || v == "dir" => v.to_string(),
+            _ => bail!(
+                "unknown sync backend '{}' (expected git, dir or copy:<path>)",
+                v
+            ),
+        },
+        "branch" if v.contains(char::is_whitespace) => bail!("invalid branch name '{}'", v),
+        "machine" if !crate::config_merge::valid_machine_id(v) => {
+            bail!("invalid machine id '{}'", v)
+        }
+        "theme" => {
+            let name = v.to_lowercase();
+            let known: Vec<String> = crate::tui::theme_options()
+                .iter()
+                .map(|t| t.to_lowercase())
+                .collect();
+            if !known.contains(&name) {
+                bail!("unknown theme '{}' (expected {})", v, known.join(", "));
+            }
+            name
+        }
+        "sudo" if crate::domain::Escalation::parse(v).is_none() => {
+            bail!(
+                "unknown escalation tool '{}' (expected sudo, doas, run0 or none)",
+                v
+            )
+        }
+        "root" if crate::domain::RootPolicy::parse(v).is_none() => {
+            bail!(
+                "unknown root policy '{}' (expected auto, always or never)",
+                v
+            )
+        }
+        "forge" if crate::cmd_connect::forge_host(v).is_none() => {
+            bail!(
+                "unknown forge '{}' (expected github, gitlab, codeberg, gitea or a host)",
+                v
+            )
+        }
+        _ => v.to_string(),
+    })
+}
+
+/// Store a setting in config.toml, replacing an earlier value in place.
+pub fn set(key: &str, value: &str) -> Result<String> {
+    if find(key).is_none() {
+        bail!("unknown setting '{}' (see 'ralf config list')", key);
+    }
+    let value = validate(key, value)?;
+    let line = format!("{} = {}", key, quote(&value));
+    let text = fs::read_to_string(path()).unwrap_or_default();
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    for l in text.lines() {
+        if is_key_line(l, key) {
+            if !replaced {
+                lines.push(line.clone());
+                replaced = true;
+            }
+        } else {
+            lines.push(l.to_string());
+        }
+    }
+    if !replaced {
+        lines.push(line);
+    }
+    write(&lines)?;
+    Ok(value)
+}
+
+/// Drop a setting from config.toml; false when it was not there.
+pub fn unset(key: &str) -> Result<bool> {
+    let Ok(text) = fs::read_to_string(path()) else {
+        return Ok(false);
+    };
+    let lines: Vec<String> = text
+        .lines()
+        .filter(|l| !is_key_line(l, key))
+        .map(String::from)
+        .collect();
+    if lines.len() == text.lines().count() {
+        return Ok(false);
+    }
+    write(&lines)?;
+    Ok(true)
+}
+
+fn is_key_line(line: &str, key: &str) -> bool {
+    line.split_once('=')
+        .is_some_and(|(k, _)| k.trim() == key && !line.trim_start().starts_with('#'))
+}
+
+fn write(lines: &[String]) -> Result<()> {
+    let file = path();
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    fs::write(&file, text).with_context(|| format!("cannot write {}", file.display()))
+}
+
+/// The file that records the repo: the rc file the environment names, else
+/// config.toml.
+pub fn repo_record() -> PathBuf {
+    if paths::rc_env_set() {
+        paths::env_rc_file()
+    } else {
+        path()
+    }
+}
+
+/// Exit with a hint unless a repo has been connected.
+pub fn require_repo() {
+    if value("repo").is_some() {
+        return;
+    }
+    println!("No repository recorded in {}", path().display());
+    println!("Please connect ralf to a repository first");
+    std::process::exit(1);
+}
+
+/// Record the connected repo: in the rc file when the environment names one,
+/// else in config.toml. The sync backend always goes to config.toml. Returns
+/// the file the repo was written to.
+pub fn store_repo(repo: &Path, sync: Option<&str>) -> Result<PathBuf> {
+    let stored = if paths::rc_env_set() {
+        let rc_file = paths::env_rc_file();
+        if let Some(parent) = rc_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        paths::write_rc(&rc_file, repo)?;
+        rc_file
+    } else {
+        set("repo", &repo.to_string_lossy())?;
+        path()
+    };
+    match sync {
+        Some(s) => {
+            set("sync", s)?;
+        }
+        None => {
+            unset("sync")?;
+        }
+    }
+    Ok(stored)
+}
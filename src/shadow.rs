@@ -0,0 +1,230 @@
+use crate::domain::annotations::has_annotation;
+use crate::domain::AliasBlock;
+use std::path::PathBuf;
+
+const SH_KEYWORDS: &[&str] = &[
+    "case", "coproc", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in",
+    "select", "then", "time", "until", "while",
+];
+
+const ZSH_KEYWORDS: &[&str] = &["end", "foreach", "nocorrect", "repeat"];
+
+const FISH_KEYWORDS: &[&str] = &[
+    "and", "begin", "case", "else", "end", "for", "function", "if", "not", "or", "switch", "time",
+    "while",
+];
+
+const SH_BUILTINS: &[&str] = &[
+    "alias",
+    "bg",
+    "bind",
+    "break",
+    "builtin",
+    "caller",
+    "cd",
+    "command",
+    "compgen",
+    "complete",
+    "compopt",
+    "continue",
+    "declare",
+    "dirs",
+    "disown",
+    "echo",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "getopts",
+    "hash",
+    "help",
+    "history",
+    "jobs",
+    "kill",
+    "let",
+    "local",
+    "logout",
+    "mapfile",
+    "popd",
+    "printf",
+    "pushd",
+    "pwd",
+    "read",
+    "readarray",
+    "readonly",
+    "return",
+    "set",
+    "shift",
+    "shopt",
+    "source",
+    "suspend",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unset",
+    "wait",
+];
+
+const ZSH_BUILTINS: &[&str] = &[
+    "autoload",
+    "bindkey",
+    "bye",
+    "chdir",
+    "compdef",
+    "emulate",
+    "float",
+    "functions",
+    "integer",
+    "limit",
+    "noglob",
+    "print",
+    "rehash",
+    "sched",
+    "setopt",
+    "unfunction",
+    "unhash",
+    "unlimit",
+    "unsetopt",
+    "vared",
+    "whence",
+    "where",
+    "which",
+    "zle",
+    "zmodload",
+    "zstyle",
+];
+
+const FISH_BUILTINS: &[&str] = &[
+    "abbr",
+    "argparse",
+    "bg",
+    "bind",
+    "block",
+    "break",
+    "breakpoint",
+    "builtin",
+    "cd",
+    "command",
+    "commandline",
+    "complete",
+    "contains",
+    "continue",
+    "count",
+    "echo",
+    "emit",
+    "eval",
+    "exec",
+    "exit",
+    "false",
+    "fg",
+    "functions",
+    "history",
+    "jobs",
+    "math",
+    "printf",
+    "pwd",
+    "random",
+    "read",
+    "realpath",
+    "return",
+    "set",
+    "set_color",
+    "source",
+    "status",
+    "string",
+    "test",
+    "true",
+    "ulimit",
+    "wait",
+];
+
+pub enum Shadowed {
+    /// Reserved word; defining a function with this name breaks the shell
+    Keyword(Vec<&'static str>),
+    Builtin(Vec<&'static str>),
+    Command(PathBuf),
+}
+
+pub struct Shadow {
+    pub alias: String,
+    pub what: Shadowed,
+}
+
+impl Shadow {
+    pub fn describe(&self) -> String {
+        match &self.what {
+            Shadowed::Keyword(shells) => {
+                format!(
+                    "'{}' is a reserved word in {}",
+                    self.alias,
+                    shells.join(", ")
+                )
+            }
+            Shadowed::Builtin(shells) => {
+                format!("'{}' shadows the {} builtin", self.alias, shells.join("/"))
+            }
+            Shadowed::Command(path) => {
+                format!("'{}' shadows the command {}", self.alias, path.display())
+            }
+        }
+    }
+}
+
+fn shells_with(name: &str, lists: [(&'static str, &[&[&str]]); 3]) -> Vec<&'static str> {
+    lists
+        .iter()
+        .filter(|(_, sets)| sets.iter().any(|set| set.contains(&name)))
+        .map(|(shell, _)| *shell)
+        .collect()
+}
+
+/// Top-level aliases that hide a keyword, builtin or binary on PATH.
+/// Expects the raw model so `@shadow-ok` annotations are still visible.
+pub fn find_shadows(blocks: &[AliasBlock]) -> Vec<Shadow> {
+    let mut out = Vec::new();
+    for b in blocks {
+        if has_annotation(&b.parent, "shadow-ok") {
+            continue;
+        }
+        let keyword = shells_with(
+            &b.name,
+            [
+                ("bash", &[SH_KEYWORDS]),
+                ("zsh", &[SH_KEYWORDS, ZSH_KEYWORDS]),
+                ("fish", &[FISH_KEYWORDS]),
+            ],
+        );
+        let builtin = shells_with(
+            &b.name,
+            [
+                ("bash", &[SH_BUILTINS]),
+                ("zsh", &[SH_BUILTINS, ZSH_BUILTINS]),
+                ("fish", &[FISH_BUILTINS]),
+            ],
+        );
+        let what = if !keyword.is_empty() {
+            Shadowed::Keyword(keyword)
+        } else if !builtin.is_empty() {
+            Shadowed::Builtin(builtin)
+        } else if let Ok(path) = which::which(&b.name) {
+            Shadowed::Command(path)
+        } else {
+            continue;
+        };
+        out.push(Shadow {
+            alias: b.name.clone(),
+            what,
+        });
+    }
+    out
+}
@@ -0,0 +1,215 @@
+// How `download` and `upload` move the config between machines. The backend
+// is picked at `connect` time and kept as the `sync` setting in config.toml:
+// git (the default), `dir` for a directory synced by other means (Syncthing,
+// NFS) and `copy:<path>` to mirror a local or mounted directory.
+
+use crate::cli::{DownloadArgs, UploadArgs};
+use crate::paths::{self, Paths};
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub trait SyncBackend {
+    /// Bring the local config up to date; the caller regenerates afterwards.
+    fn download(&self, p: &Paths, args: &DownloadArgs) -> Result<()>;
+    /// Publish local config changes.
+    fn upload(&self, p: &Paths, args: &UploadArgs) -> Result<()>;
+}
+
+pub struct Git;
+
+impl SyncBackend for Git {
+    fn download(&self, p: &Paths, args: &DownloadArgs) -> Result<()> {
+        crate::cmd_download::git_download(p, args)
+    }
+
+    fn upload(&self, p: &Paths, args: &UploadArgs) -> Result<()> {
+        crate::cmd_upload::git_upload(p, args)
+    }
+}
+
+/// The repo is the synced directory itself, so there is nothing to transfer.
+pub struct Dir;
+
+impl SyncBackend for Dir {
+    fn download(&self, p: &Paths, _args: &DownloadArgs) -> Result<()> {
+        println!(
+            "{} is synced outside ralf; regenerating from it",
+            paths::friendly(&p.repo_path)
+        );
+        Ok(())
+    }
+
+    fn upload(&self, p: &Paths, _args: &UploadArgs) -> Result<()> {
+        println!(
+            "{} is synced outside ralf; nothing to upload",
+            paths::friendly(&p.repo_path)
+        );
+        Ok(())
+    }
+}
+
+/// Mirror the shared files to and from `source`, like `rsync --delete` limited
+/// to the base config and the machines directory. Upload never deletes: other
+/// machines write to `source` too, so a file missing here may just be new there.
+/// Download only deletes files an earlier sync brought, never overlays made
+/// here and not uploaded yet.
+pub struct Copy {
+    pub source: PathBuf,
+}
+
+impl SyncBackend for Copy {
+    fn download(&self, p: &Paths, _args: &DownloadArgs) -> Result<()> {
+        println!(
+            "Copying from {} to {}",
+            paths::friendly(&self.source),
+            paths::friendly(&p.repo_path)
+        );
+        let synced = synced_files(&p.repo_path);
+        report(
+            mirror(&self.source, &p.repo_path, &base_name(p), &synced)?,
+            "Kept files are not in the shared directory; upload them to share them, or delete them here",
+        );
+        record_synced(&p.repo_path, &self.source, &base_name(p))?;
+        Ok(())
+    }
+
+    fn upload(&self, p: &Paths, _args: &UploadArgs) -> Result<()> {
+        println!(
+            "Copying from {} to {}",
+            paths::friendly(&p.repo_path),
+            paths::friendly(&self.source)
+        );
+        report(
+            mirror(&p.repo_path, &self.source, &base_name(p), &[])?,
+            "Kept files are gone here only; delete them in the shared directory to drop them everywhere",
+        );
+        record_synced(&p.repo_path, &p.repo_path, &base_name(p))?;
+        Ok(())
+    }
+}
+
+/// Backend named by the `sync` setting.
+pub fn backend() -> Result<Box<dyn SyncBackend>> {
+    let found = crate::settings::lookup("sync");
+    let setting = found.as_ref().map(|(v, _)| v.clone());
+    Ok(match setting.as_deref() {
+        None | Some("git") => Box::new(Git),
+        Some("dir") => Box::new(Dir),
+        Some(s) => match s.strip_prefix("copy:") {
+            Some(source) if !source.is_empty() => Box::new(Copy {
+                source: PathBuf::from(source),
+            }),
+            _ => bail!(
+                "unknown sync backend '{}' in {} (expected git, dir or copy:<path>)",
+                s,
+                found.map(|(_, from)| from.describe()).unwrap_or_default()
+            ),
+        },
+    })
+}
+
+fn base_name(p: &Paths) -> String {
+    p.config_file
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "ralf.conf".to_string())
+}
+
+/// Shared files under `root`, repo-relative: the base config and the files in
+/// machines/ and machines/groups/.
+fn shared_files(root: &Path, base: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    if root.join(base).is_file() {
+        out.push(base.to_string());
+    }
+    for dir in ["machines", "machines/groups"] {
+        if let Ok(entries) = fs::read_dir(root.join(dir)) {
+            let mut names: Vec<String> = entries
+                .flatten()
+                .filter(|e| e.path().is_file())
+                .map(|e| format!("{}/{}", dir, e.file_name().to_string_lossy()))
+                .collect();
+            names.sort();
+            out.extend(names);
+        }
+    }
+    out
+}
+
+/// Make the shared files under `to` match `from`; of the files only `to` has,
+/// those listed in `removable` are removed and the others kept. Returns
+/// (action, file) for each file that was added, updated, removed or kept.
+pub fn mirror(
+    from: &Path,
+    to: &Path,
+    base: &str,
+    removable: &[String],
+) -> Result<Vec<(&'static str, String)>> {
+    if !from.join(base).is_file() {
+        bail!("no {} in {}", base, from.display());
+    }
+    let wanted = shared_files(from, base);
+    let mut changes = Vec::new();
+    for rel in &wanted {
+        let content = fs::read(from.join(rel))?;
+        let dest = to.join(rel);
+        let action = match fs::read(&dest) {
+            Ok(old) if old == content => continue,
+            Ok(_) => "updated",
+            Err(_) => "added",
+        };
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, content)?;
+        changes.push((action, rel.clone()));
+    }
+    for rel in shared_files(to, base) {
+        if wanted.contains(&rel) {
+            continue;
+        }
+        if removable.contains(&rel) {
+            fs::remove_file(to.join(&rel))?;
+            changes.push(("removed", rel));
+        } else {
+            changes.push(("kept", rel));
+        }
+    }
+    Ok(changes)
+}
+
+/// Where a copy records the shared files it had after its last sync.
+fn synced_list(repo: &Path) -> PathBuf {
+    repo.join(".ralf-synced")
+}
+
+/// Shared files the last sync left in `repo`, both here and in the source.
+pub fn synced_files(repo: &Path) -> Vec<String> {
+    fs::read_to_string(synced_list(repo))
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Remember which shared files `repo` and its source both have after
+/// mirroring from `from`: all of the ones in `from`.
+pub fn record_synced(repo: &Path, from: &Path, base: &str) -> Result<()> {
+    let files = shared_files(from, base);
+    let mut out = files.join("\n");
+    out.push('\n');
+    fs::write(synced_list(repo), out)?;
+    Ok(())
+}
+
+fn report(changes: Vec<(&'static str, String)>, kept_note: &str) {
+    if changes.is_empty() {
+        println!("Already up to date");
+    }
+    let kept = changes.iter().any(|(action, _)| *action == "kept");
+    for (action, file) in changes {
+        println!("  {} {}", action, file);
+    }
+    if kept {
+        println!("{}", kept_note);
+    }
+}
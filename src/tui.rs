@@ -4,7 +4,6 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use dirs::home_dir;
 use ratatui::widgets::canvas::{Canvas, Circle};
 use ratatui::{
     backend::CrosstermBackend,
@@ -15,9 +14,7 @@ use ratatui::{
     Terminal,
 };
 use ratatui_core::symbols::marker::Marker;
-use std::fs;
 use std::io::{stdout, Stdout};
-use std::path::PathBuf;
 
 const TRANS_BLUE: Color = Color::Rgb(0x5B, 0xCF, 0xFA); // #5BCFFA
 const TRANS_PINK: Color = Color::Rgb(0xF5, 0xA9, 0xB8); // #F5A9B8
@@ -32,10 +29,6 @@ enum ThemeName {
     Progress,
 }
 
-fn theme_file() -> PathBuf {
-    home_dir().unwrap_or_default().join(".ralf_theme")
-}
-
 fn parse_theme(s: &str) -> ThemeName {
     match s.trim().to_lowercase().as_str() {
         "trans" => ThemeName::Trans,
@@ -49,17 +42,13 @@ fn parse_theme(s: &str) -> ThemeName {
 }
 
 pub fn current_theme() -> ThemeName {
-    if let Ok(s) = std::env::var("RALF_THEME") {
-        return parse_theme(&s);
-    }
-    if let Ok(s) = fs::read_to_string(theme_file()) {
-        return parse_theme(&s);
-    }
-    ThemeName::Trans
+    crate::settings::value("theme")
+        .map(|s| parse_theme(&s))
+        .unwrap_or(ThemeName::Trans)
 }
 
 pub fn set_theme_by_name(name: &str) -> Result<()> {
-    fs::write(theme_file(), name.trim().to_lowercase())?;
+    crate::settings::set("theme", name)?;
     Ok(())
 }
 
@@ -183,9 +172,9 @@ pub enum ConnectChoice {
     Abort,
 }
 
-pub fn choose_github_protocol() -> Result<ConnectChoice> {
+pub fn choose_protocol(host: &str) -> Result<ConnectChoice> {
     let items = ["SSH", "HTTPS", "Abort"];
-    match list_select("Select GitHub protocol", &items)? {
+    match list_select(&format!("Select protocol for {}", host), &items)? {
         Some(0) => Ok(ConnectChoice::Ssh),
         Some(1) => Ok(ConnectChoice::Https),
         Some(2) | None => Ok(ConnectChoice::Abort),
@@ -0,0 +1,129 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+use common::{git, has_git};
+
+/// A bare remote whose `main` holds personal aliases and `work` work ones.
+/// Returns its file:// URL.
+fn setup_remote(temp: &TempDir) -> String {
+    let srv = temp.child("srv");
+    srv.create_dir_all().unwrap();
+    git(
+        srv.path(),
+        &["init", "-q", "--bare", "-b", "main", "ralf-conf.git"],
+    );
+    git(srv.path(), &["clone", "-q", "ralf-conf.git", "seed"]);
+    let seed = srv.child("seed");
+    seed.child("ralf.conf").write_str("g: git\n").unwrap();
+    git(seed.path(), &["add", "-A"]);
+    git(seed.path(), &["commit", "-qm", "personal"]);
+    git(seed.path(), &["push", "-q", "origin", "HEAD:main"]);
+    git(seed.path(), &["switch", "-q", "-c", "work"]);
+    seed.child("ralf.conf").write_str("k: kubectl\n").unwrap();
+    git(seed.path(), &["commit", "-qam", "work"]);
+    git(seed.path(), &["push", "-q", "origin", "work"]);
+    format!("file://{}", srv.child("ralf-conf.git").path().display())
+}
+
+fn ralf(temp: &TempDir) -> Command {
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", temp.child("ralfrc").path())
+        .env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn connect_checks_out_the_requested_branch_and_remembers_it() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    let url = setup_remote(&temp);
+    ralf(&temp)
+        .args(["connect", &url, "--branch", "work", "--yes"])
+        .assert()
+        .success();
+    let aliases = temp.child("config/ralf/aliases.sh");
+    aliases.assert(predicate::str::contains("k()"));
+
+    ralf(&temp)
+        .arg("branch")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("On branch work").and(predicate::str::contains("  main")));
+
+    // A fresh clone picks the remembered branch without being told again
+    std::fs::remove_dir_all(temp.child("data/ralf/repo").path()).unwrap();
+    ralf(&temp)
+        .args(["connect", &url, "--yes"])
+        .assert()
+        .success();
+    let head = git(
+        temp.child("data/ralf/repo").path(),
+        &["branch", "--show-current"],
+    );
+    assert_eq!(head.trim(), "work");
+
+    // Another repo does not inherit it
+    let srv = temp.child("srv");
+    git(
+        srv.path(),
+        &["init", "-q", "--bare", "-b", "main", "other.git"],
+    );
+    git(
+        srv.child("seed").path(),
+        &["push", "-q", "../other.git", "main"],
+    );
+    let other = format!("file://{}", srv.child("other.git").path().display());
+    ralf(&temp)
+        .args(["connect", &other, "--dir", "other", "--yes"])
+        .assert()
+        .success();
+    temp.child("config/ralf/config.toml")
+        .assert(predicate::str::contains("branch").not());
+}
+
+#[test]
+fn branch_switches_the_tracked_branch_and_regenerates() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    let url = setup_remote(&temp);
+    ralf(&temp)
+        .args(["connect", &url, "--yes"])
+        .assert()
+        .success();
+    let aliases = temp.child("config/ralf/aliases.sh");
+    aliases.assert(predicate::str::contains("g()"));
+
+    ralf(&temp)
+        .args(["branch", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to branch work"));
+    aliases.assert(predicate::str::contains("k()").and(predicate::str::contains("g()").not()));
+    temp.child("config/ralf/config.toml")
+        .assert(predicate::str::contains("branch = \"work\"\n"));
+
+    ralf(&temp)
+        .args(["branch", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no branch 'nope'"));
+
+    // Download puts the repo back on the tracked branch if it wandered off
+    git(
+        temp.child("data/ralf/repo").path(),
+        &["switch", "-q", "main"],
+    );
+    ralf(&temp)
+        .arg("download")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Switching to branch work"));
+    aliases.assert(predicate::str::contains("k()"));
+}
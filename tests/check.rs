@@ -0,0 +1,68 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+#[test]
+fn check_reports_shadowed_names() {
+    let temp = TempDir::new().unwrap();
+    temp.child("ralf.conf")
+        .write_str("g: git\ntest: echo t\nif: echo no\nsh: bash\n")
+        .unwrap();
+
+    common::cmd()
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "warning: 'test' shadows the bash/zsh/fish builtin",
+        ))
+        .stdout(predicate::str::contains(
+            "warning: 'if' is a reserved word in bash, zsh, fish",
+        ))
+        .stdout(predicate::str::contains(
+            "warning: 'sh' shadows the command",
+        ))
+        .stdout(predicate::str::contains("'g'").not());
+}
+
+#[test]
+fn check_respects_shadow_ok() {
+    let temp = TempDir::new().unwrap();
+    temp.child("ralf.conf")
+        .write_str("g: git\ntest: @shadow-ok echo t\n")
+        .unwrap();
+
+    common::cmd()
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .success()
+        .stdout("No problems found in 2 aliases\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .args(["which", "test"])
+        .assert()
+        .success()
+        .stdout("echo t\n");
+}
+
+#[test]
+fn save_warns_about_shadowing() {
+    let temp = TempDir::new().unwrap();
+    temp.child("ralf.conf")
+        .write_str("cd: echo nope\n")
+        .unwrap();
+
+    common::ralf(&temp)
+        .env("RALF_ALIASES_FILE", temp.child("aliases.txt").path())
+        .arg("save")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "warning: 'cd' shadows the bash/zsh/fish builtin (add @shadow-ok to silence)",
+        ));
+}
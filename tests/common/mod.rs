@@ -73,3 +73,55 @@ pub fn prepend_to_path(dir: &Path) -> String {
     let old = env::var("PATH").unwrap_or_default();
     format!("{}:{}", dir.display(), old)
 }
+
+/// ralf run in `temp`, with HOME, XDG_CONFIG_HOME and XDG_DATA_HOME inside it
+/// so nothing reaches the real home directory, a git identity for commits,
+/// and no overrides inherited from the shell running the tests.
+pub fn ralf(temp: &TempDir) -> Command {
+    let mut c = cmd();
+    c.current_dir(temp.path())
+        .env("HOME", temp.path())
+        .env("XDG_CONFIG_HOME", temp.child("config").path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
+        .env("SHELL", "/bin/bash")
+        .env("GIT_AUTHOR_NAME", "t")
+        .env("GIT_AUTHOR_EMAIL", "t@example.com")
+        .env("GIT_COMMITTER_NAME", "t")
+        .env("GIT_COMMITTER_EMAIL", "t@example.com");
+    for var in [
+        "RALF_RC_FILE",
+        "ralf_RC_FILE",
+        "ALF_RC_FILE",
+        "RALF_ALIASES_FILE",
+        "ralf_ALIASES_FILE",
+        "ALF_ALIASES_FILE",
+        "RALF_MACHINE",
+        "ALF_MACHINE",
+        "ralf_MACHINE",
+        "alf_MACHINE",
+        "RALF_THEME",
+        "RALF_FORGE",
+        "RALF_UPGRADE_URL",
+        "RALF_GENERATION",
+        "VISUAL",
+        "EDITOR",
+    ] {
+        c.env_remove(var);
+    }
+    c
+}
+
+/// `git` in `dir` with a throwaway identity; returns its stdout.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+pub fn has_git() -> bool {
+    Command::new("git").arg("--version").output().is_ok()
+}
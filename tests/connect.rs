@@ -16,6 +16,9 @@ fn connect_https_username_noninteractive() {
     common::cmd()
         .current_dir(temp.path())
         .env("PATH", common::prepend_to_path(bin_dir.path()))
+        .env("HOME", temp.path())
+        .env("XDG_CONFIG_HOME", temp.child("config").path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
         .env("ALF_RC_FILE", alfrc.path())
         .env("ALF_ALIASES_FILE", aliases.path())
         .args(["connect", "DannyBen", "--https"])
@@ -28,7 +31,9 @@ fn connect_https_username_noninteractive() {
         .stdout(predicate::str::contains("Saving to"));
 
     let rc_content = fs::read_to_string(alfrc.path()).unwrap();
-    assert!(rc_content.ends_with("/ralf-conf") || rc_content.ends_with("\\ralf-conf"));
+    assert!(rc_content.trim_end().ends_with("/data/ralf/repo"));
+    temp.child("data/ralf/repo/ralf.conf")
+        .assert(predicates::path::exists());
     aliases.assert(predicates::path::exists());
 }
 
@@ -43,6 +48,9 @@ fn connect_full_url_yes() {
     common::cmd()
         .current_dir(temp.path())
         .env("PATH", common::prepend_to_path(bin_dir.path()))
+        .env("HOME", temp.path())
+        .env("XDG_CONFIG_HOME", temp.child("config").path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
         .env("ALF_RC_FILE", alfrc.path())
         .env("ALF_ALIASES_FILE", aliases.path())
         .args([
@@ -58,6 +66,35 @@ fn connect_full_url_yes() {
         .stdout(predicate::str::contains("Storing location in"))
         .stdout(predicate::str::contains("Saving to"));
     let rc_content = std::fs::read_to_string(alfrc.path()).unwrap();
-    // Migrated to ralf-conf
-    assert!(rc_content.ends_with("/ralf-conf") || rc_content.ends_with("\\ralf-conf"));
+    // Cloned to the stable location whatever the repo is called
+    assert!(rc_content.trim_end().ends_with("/data/ralf/repo"));
+}
+
+#[test]
+fn connect_keeps_a_clone_of_another_repo() {
+    let temp = TempDir::new().unwrap();
+    let bin_dir = temp.child("bin");
+    common::write_fake_git(bin_dir.path());
+    let connect = |url: &str, origin: &str| {
+        let mut c = common::ralf(&temp);
+        c.env("PATH", common::prepend_to_path(bin_dir.path()))
+            .env("FAKE_REMOTE_URL", origin)
+            .args(["connect", url, "--yes"]);
+        c
+    };
+    let first = "https://github.com/DannyBen/ralf-conf.git";
+    connect(first, "").assert().success();
+    temp.child("data/ralf/repo/.git").create_dir_all().unwrap();
+
+    connect("https://example.com/me/dots.git", first)
+        .assert()
+        .failure()
+        .stderr(
+            predicate::str::contains("is a clone of https://github.com/DannyBen/ralf-conf.git")
+                .and(predicate::str::contains("--dir")),
+        );
+    connect("https://github.com/DannyBen/ralf-conf", first)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipping clone"));
 }
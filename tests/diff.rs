@@ -0,0 +1,155 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    if !repo.child("ralf.conf").path().exists() {
+        repo.child("ralf.conf")
+            .write_str("g: git\n  s: status\n  l: log\ndc: docker compose\n")
+            .unwrap();
+        repo.child("machines/web1.conf")
+            .write_str("g: hub\n  s: status -sb\nupd: @root apt update\n")
+            .unwrap();
+    }
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn diff_between_machines() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["diff", "--machine", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "--- machine box\n+++ machine web1\n",
+        ))
+        .stdout(predicate::str::contains("~ g    git\n       hub\n"))
+        .stdout(predicate::str::contains("+ upd  sudo apt update\n"));
+    setup(&temp)
+        .args(["diff", "--machine", "web1", "--machine", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences"));
+}
+
+#[test]
+fn diff_against_revision() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+    };
+    if git(&["init", "-q"]).is_err() {
+        return; // git not installed
+    }
+    git(&["add", "-A"]).unwrap();
+    git(&["commit", "-qm", "init"]).unwrap();
+    repo.child("ralf.conf")
+        .write_str("g: git\n  s: status\n  d: diff\n")
+        .unwrap();
+
+    setup(&temp)
+        .arg("diff")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- HEAD (box)"))
+        .stdout(predicate::str::contains(
+            "+ g d  diff\n- g l  log\n- dc   docker compose\n",
+        ));
+    setup(&temp)
+        .args(["diff", "--rev", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown revision 'nope'"));
+    setup(&temp)
+        .args(["diff", "--rev", "HEAD", "--machine", "../web1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid machine id '../web1'"));
+
+    // The local overlay is not versioned, and the label says it is left out
+    repo.child("ralf.local.conf")
+        .write_str("l: ls -la\n")
+        .unwrap();
+    setup(&temp)
+        .arg("diff")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "+++ working tree (box, without ralf.local.conf)",
+        ))
+        .stdout(predicate::str::contains("ls -la").not());
+}
+
+#[test]
+fn diff_against_revision_includes_group_overlays() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("machines/rules")
+        .write_str("@lab: machine=box\n")
+        .unwrap();
+    repo.child("machines/groups/lab.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+    };
+    if git(&["init", "-q"]).is_err() {
+        return; // git not installed
+    }
+    git(&["add", "-A"]).unwrap();
+    git(&["commit", "-qm", "init"]).unwrap();
+    repo.child("machines/groups/lab.conf")
+        .write_str("k: kubecolor\n")
+        .unwrap();
+
+    setup(&temp)
+        .arg("diff")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~ k  kubectl\n     kubecolor\n"));
+}
+
+#[test]
+fn diff_generated_file() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp).arg("save").assert().success();
+    setup(&temp)
+        .args(["diff", "--generated"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences"));
+
+    temp.child("ralf-conf/ralf.conf")
+        .write_str("g: git\n  s: status -sb\n")
+        .unwrap();
+    setup(&temp)
+        .args(["diff", "--generated", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"kind\": \"changed\""))
+        .stdout(predicate::str::contains(
+            "\"new\": \"git status -sb \\\"$@\\\"\"",
+        ))
+        .stdout(predicate::str::contains("\"kind\": \"removed\""));
+}
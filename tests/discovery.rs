@@ -0,0 +1,146 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::path::Path;
+use std::process::Command;
+mod common;
+
+/// ralf run from `cwd` with nothing pointing at a config but the tree itself.
+fn ralf(temp: &TempDir, cwd: &Path) -> Command {
+    let mut c = common::ralf(temp);
+    c.current_dir(cwd);
+    c
+}
+
+#[test]
+fn config_is_found_in_a_parent_directory() {
+    let temp = TempDir::new().unwrap();
+    temp.child("proj/ralf.conf").write_str("g: git\n").unwrap();
+    let nested = temp.child("proj/src/deep");
+    nested.create_dir_all().unwrap();
+    ralf(&temp, nested.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("g()"));
+
+    // A ralf-conf checkout next to a parent counts as well
+    temp.child("work/ralf-conf/ralf.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+    let nested = temp.child("work/app");
+    nested.create_dir_all().unwrap();
+    ralf(&temp, nested.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("k()"));
+}
+
+#[test]
+fn rc_file_env_wins_over_walking_up() {
+    let temp = TempDir::new().unwrap();
+    temp.child("proj/ralf.conf").write_str("g: git\n").unwrap();
+    temp.child("other/ralf.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&format!("{}\n", temp.child("other").path().display()))
+        .unwrap();
+    ralf(&temp, temp.child("proj").path())
+        .env("RALF_RC_FILE", rc.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("k()").and(predicate::str::contains("g()").not()));
+}
+
+#[test]
+fn home_rc_then_the_data_dir_are_used_outside_any_config_tree() {
+    let temp = TempDir::new().unwrap();
+    let elsewhere = temp.child("elsewhere");
+    elsewhere.create_dir_all().unwrap();
+    temp.child("data/ralf/repo/ralf.conf")
+        .write_str("d: docker\n")
+        .unwrap();
+    ralf(&temp, elsewhere.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("d()"));
+
+    temp.child("dots/ralf.conf").write_str("g: git\n").unwrap();
+    temp.child(".ralfrc")
+        .write_str(&format!("{}\n", temp.child("dots").path().display()))
+        .unwrap();
+    ralf(&temp, elsewhere.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("g()").and(predicate::str::contains("d()").not()));
+}
+
+#[test]
+fn connect_clones_to_the_data_dir_or_dir_and_works_from_anywhere() {
+    let temp = TempDir::new().unwrap();
+    let bin_dir = temp.child("bin");
+    common::write_fake_git(bin_dir.path());
+    let somewhere = temp.child("somewhere");
+    somewhere.create_dir_all().unwrap();
+
+    ralf(&temp, somewhere.path())
+        .env("PATH", common::prepend_to_path(bin_dir.path()))
+        .args(["connect", "me", "--https"])
+        .assert()
+        .success();
+    temp.child("data/ralf/repo/ralf.conf")
+        .assert(predicate::path::exists());
+    assert!(!somewhere.child("ralf-conf").path().exists());
+    let settings = temp.child("config/ralf/config.toml");
+    settings.assert(predicate::str::contains("/data/ralf/repo\"\n"));
+
+    ralf(&temp, somewhere.path())
+        .env("PATH", common::prepend_to_path(bin_dir.path()))
+        .args(["connect", "me", "--https", "--dir", "dots"])
+        .assert()
+        .success();
+    somewhere
+        .child("dots/ralf.conf")
+        .assert(predicate::path::exists());
+    settings.assert(predicate::str::contains("/somewhere/dots\"\n"));
+
+    // Commands run elsewhere still find the connected config
+    ralf(&temp, temp.path()).arg("save").assert().success();
+}
+
+#[test]
+fn walking_up_leaves_legacy_names_and_the_rc_alone() {
+    let temp = TempDir::new().unwrap();
+    temp.child("proj/alf.conf").write_str("g: git\n").unwrap();
+    temp.child("proj/alf-conf/alf.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+    let nested = temp.child("proj/src");
+    nested.create_dir_all().unwrap();
+    ralf(&temp, nested.path())
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("g()"));
+    temp.child("proj/alf.conf")
+        .assert(predicate::path::exists());
+    assert!(!temp.child("proj/ralf.conf").path().exists());
+    assert!(!temp.child("proj/ralf-conf").path().exists());
+
+    // An rc naming a repo without a config keeps naming it
+    let rc = temp.child("ralfrc");
+    let missing = format!("{}\n", temp.child("gone").path().display());
+    rc.write_str(&missing).unwrap();
+    ralf(&temp, nested.path())
+        .env("RALF_RC_FILE", rc.path())
+        .arg("generate")
+        .assert()
+        .success();
+    rc.assert(missing.as_str());
+}
@@ -0,0 +1,143 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+use common::{git, has_git};
+
+/// Two clones of one bare remote: `ralf-conf` is ours, `other` another machine.
+/// Commits `ours` locally and pushes `theirs` from the other clone.
+fn setup(temp: &TempDir, base: &str, ours: &str, theirs: &str) {
+    setup_file(temp, "ralf.conf", base, ours, theirs);
+}
+
+/// `setup` for any file in the repo, next to a base config of its own.
+fn setup_file(temp: &TempDir, file: &str, base: &str, ours: &str, theirs: &str) {
+    git(temp.path(), &["init", "-q", "--bare", "remote.git"]);
+    git(temp.path(), &["clone", "-q", "remote.git", "ralf-conf"]);
+    let repo = temp.child("ralf-conf");
+    if file != "ralf.conf" {
+        repo.child("ralf.conf").write_str("g: git\n").unwrap();
+    }
+    repo.child(file).write_str(base).unwrap();
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "-qm", "base"]);
+    git(repo.path(), &["push", "-q", "origin", "HEAD"]);
+
+    git(temp.path(), &["clone", "-q", "remote.git", "other"]);
+    let other = temp.child("other");
+    other.child(file).write_str(theirs).unwrap();
+    git(other.path(), &["commit", "-qam", "theirs"]);
+    git(other.path(), &["push", "-q", "origin", "HEAD"]);
+
+    repo.child(file).write_str(ours).unwrap();
+    git(repo.path(), &["commit", "-qam", "ours"]);
+}
+
+fn download(temp: &TempDir) -> Command {
+    let rc = temp.child("ralfrc");
+    rc.write_str(&temp.child("ralf-conf").path().to_string_lossy())
+        .unwrap();
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path())
+        .env("RALF_MACHINE", "box")
+        .arg("download");
+    c
+}
+
+#[test]
+fn download_merges_different_aliases_changed_on_both_sides() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(
+        &temp,
+        "g: git\n  s: status\n  l: log\ndc: docker compose\n",
+        "# mine\ng: git\n  s: status -sb\n  l: log\ndc: docker compose\n",
+        "g: git\n  s: status\n  l: log --oneline\ndc: docker compose\nk: kubectl\n",
+    );
+    download(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merged ralf.conf"));
+    let conf = std::fs::read_to_string(temp.child("ralf-conf/ralf.conf").path()).unwrap();
+    assert_eq!(
+        conf,
+        "# mine\ng: git\n  s: status -sb\n  l: log --oneline\ndc: docker compose\nk: kubectl\n"
+    );
+    let repo = temp.child("ralf-conf");
+    assert!(git(repo.path(), &["status", "--porcelain", "ralf.conf"]).is_empty());
+    assert_eq!(
+        git(repo.path(), &["log", "-1", "--format=%p"])
+            .split(' ')
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn download_leaves_real_conflicts_to_the_user() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    let ours = "g: git\n  s: status -sb\n";
+    setup(
+        &temp,
+        "g: git\n  s: status\n",
+        ours,
+        "g: git\n  s: status --short\n",
+    );
+    download(&temp).assert().failure().stderr(
+        predicate::str::contains("g s (was 'status', ours 'status -sb', theirs 'status --short')")
+            .and(predicate::str::contains("--ours or --theirs")),
+    );
+    let path = temp.child("ralf-conf/ralf.conf");
+    assert_eq!(std::fs::read_to_string(path.path()).unwrap(), ours);
+
+    download(&temp).arg("--theirs").assert().success();
+    assert_eq!(
+        std::fs::read_to_string(path.path()).unwrap(),
+        "g: git\n  s: status --short\n"
+    );
+}
+
+#[test]
+fn download_keeps_an_alias_we_changed_but_they_removed_with_ours() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(
+        &temp,
+        "g: git\ndc: docker compose\n  up: up -d\n",
+        "g: git\ndc: docker compose\n  up: up -d --build\n",
+        "g: git\n",
+    );
+    download(&temp).arg("--ours").assert().success();
+    let conf = std::fs::read_to_string(temp.child("ralf-conf/ralf.conf").path()).unwrap();
+    assert_eq!(conf, "g: git\ndc: docker compose\n  up: up -d --build\n");
+}
+
+#[test]
+fn download_merges_group_overlays_too() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup_file(
+        &temp,
+        "machines/groups/lab.conf",
+        "k: kubectl\n  p: get pods\n",
+        "k: kubectl\n  a: apply -f\n  p: get pods\n",
+        "k: kubectl\n  n: get nodes\n  p: get pods\n",
+    );
+    download(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merged machines/groups/lab.conf"));
+    temp.child("ralf-conf/machines/groups/lab.conf")
+        .assert("k: kubectl\n  a: apply -f\n  p: get pods\n  n: get nodes\n");
+}
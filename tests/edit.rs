@@ -0,0 +1,133 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str("# Git\ng: git\n  s: status\n")
+        .unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn valid_edit_is_applied_and_saved() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .arg("edit")
+        .env("EDITOR", "sed -i s/status/status-sb/")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved"));
+    temp.child("ralf-conf/ralf.conf")
+        .assert("# Git\ng: git\n  s: status-sb\n");
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::str::contains("git status-sb"));
+}
+
+#[test]
+fn invalid_edit_is_discarded() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .arg("edit")
+        .env("VISUAL", "sed -i -e s/status/log/ -e '$a<<<<<<< HEAD'")
+        .env("EDITOR", "false")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "ralf.conf:4: unresolved merge conflict marker",
+        ));
+    temp.child("ralf-conf/ralf.conf")
+        .assert("# Git\ng: git\n  s: status\n");
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn unchanged_edit_does_not_regenerate() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .arg("edit")
+        .env("EDITOR", "true")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes"));
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::path::missing());
+}
+
+#[cfg(unix)]
+fn fake_vi(temp: &TempDir) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    let vi = temp.child("bin/vi");
+    vi.write_str("#!/bin/sh\necho \"$@\" > \"$(dirname \"$0\")/args\"\n")
+        .unwrap();
+    std::fs::set_permissions(vi.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    vi.path().to_string_lossy().to_string()
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_alias_opens_defining_layer_at_its_line() {
+    let temp = TempDir::new().unwrap();
+    let vi = fake_vi(&temp);
+    temp.child("ralf-conf/machines/box.conf")
+        .write_str("g: hub\n  s: status -sb\n")
+        .unwrap();
+    setup(&temp)
+        .args(["edit", "g", "s"])
+        .env("EDITOR", &vi)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes to"))
+        .stdout(predicate::str::contains("box.conf"));
+    temp.child("bin/args")
+        .assert(predicate::str::is_match(r"^\+2 .*box\.conf\n$").unwrap());
+
+    setup(&temp)
+        .args(["edit", "nope"])
+        .env("EDITOR", &vi)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("No such alias: nope"));
+}
+
+#[test]
+fn edit_missing_layer_creates_it_from_template() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["edit", "machine", "web1"])
+        .env("EDITOR", "true")
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/web1.conf")
+        .assert(predicate::str::starts_with("# Overlay for machine 'web1'"));
+
+    setup(&temp)
+        .args(["edit", "local"])
+        .env("EDITOR", "sed -i '$ag: git2'")
+        .assert()
+        .success();
+    temp.child("ralf-conf/ralf.local.conf")
+        .assert(predicate::str::ends_with("g: git2\n"));
+
+    temp.child("ralf-conf/machines/rules")
+        .write_str("@work: machine=b*\n")
+        .unwrap();
+    setup(&temp)
+        .args(["edit", "group", "work"])
+        .env("EDITOR", "sed -i '$ak: kubectl'")
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/groups/work.conf")
+        .assert(predicate::str::starts_with("# Overlay for group 'work'"));
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::str::contains("kubectl"));
+}
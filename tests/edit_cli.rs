@@ -0,0 +1,93 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let rc = temp.child("ralfrc");
+    rc.write_str(&temp.child("ralf-conf").path().to_string_lossy())
+        .unwrap();
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn add_set_rm_keep_comments_and_regenerate() {
+    let temp = TempDir::new().unwrap();
+    let conf = temp.child("ralf-conf/ralf.conf");
+    conf.write_str(
+        "# Git shortcuts\ng:   git\n    # pretty log\n    l: log --all\n    s: status\n",
+    )
+    .unwrap();
+
+    setup(&temp)
+        .args(["add", "g", "d", "diff"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added 'g d'"));
+    setup(&temp).args(["set", "g", "hub"]).assert().success();
+    setup(&temp)
+        .args(["rm", "g", "l"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 'g l'"));
+
+    conf.assert("# Git shortcuts\ng:   hub\n    s: status\n    d: diff\n");
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::str::contains("hub diff"));
+}
+
+#[test]
+fn add_refuses_duplicates_and_bad_names() {
+    let temp = TempDir::new().unwrap();
+    let conf = temp.child("ralf-conf/ralf.conf");
+    conf.write_str("g: git\n  s: status\n").unwrap();
+
+    setup(&temp)
+        .args(["add", "g", "s", "status -sb"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --force"));
+    setup(&temp)
+        .args(["add", "b@d", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid name"));
+    conf.assert("g: git\n  s: status\n");
+    setup(&temp)
+        .args(["add", "x", "echo hi", "--machine", "../../escaped"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid machine id '../../escaped'",
+        ));
+    setup(&temp)
+        .args(["rm", "g", "--machine", "../escaped"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid machine id"));
+    assert!(!temp.child("escaped.conf").path().exists());
+
+    setup(&temp)
+        .args(["add", "g", "s", "status -sb", "--force"])
+        .assert()
+        .success();
+    conf.assert("g: git\n  s: status -sb\n");
+}
+
+#[test]
+fn add_sub_to_machine_overlay_copies_parent() {
+    let temp = TempDir::new().unwrap();
+    temp.child("ralf-conf/ralf.conf")
+        .write_str("dc: docker compose\n")
+        .unwrap();
+
+    setup(&temp)
+        .args(["add", "dc", "up", "up -d", "--machine", "box"])
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/box.conf")
+        .assert("dc: docker compose\n  up: up -d\n");
+}
@@ -0,0 +1,75 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str("g: git\n  l: log\n  sh: !echo $(date) && true\nls: ls --color\n")
+        .unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::cmd();
+    c.current_dir(temp.path())
+        .env("RALF_RC_FILE", rc.path())
+        .env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn expand_shows_branch_and_command_line_per_shell() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["expand", "--", "g", "l", "--oneline", "a b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "bash/zsh\n  branch:  l)\n  runs:    git log --oneline 'a b'\n",
+        ))
+        .stdout(predicate::str::contains(
+            "fish\n  branch:  case l\n  runs:    git log --oneline 'a b'\n",
+        ));
+    setup(&temp)
+        .args(["expand", "g", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "branch:  *)\n  runs:    git status\n",
+        ));
+}
+
+#[test]
+fn expand_single_shell_with_rewrite_and_shim() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["expand", "--shell", "fish", "--", "ls", "-la"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("runs:    command ls --color -la"))
+        .stdout(predicate::str::contains("bash/zsh").not());
+    // As the last words it still picks the shell
+    setup(&temp)
+        .args(["expand", "--", "ls", "-la", "--shell", "fish"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$ ls -la\n"))
+        .stdout(predicate::str::contains(
+            "runs:    command ls --color -la\n",
+        ))
+        .stdout(predicate::str::contains("bash/zsh").not());
+    setup(&temp)
+        .args(["expand", "--shell", "fish", "g", "sh", "x"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "runs:    bash -lc 'echo $(date) && true \"$@\"' -- x",
+        ));
+    setup(&temp)
+        .args(["expand", "--shell", "bash", "g", "sh", "x"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with:    $@ = x"));
+}
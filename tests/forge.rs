@@ -0,0 +1,107 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+mod common;
+
+fn connect(temp: &TempDir, args: &[&str]) -> Command {
+    let bin_dir = temp.child("bin");
+    if !bin_dir.path().exists() {
+        common::write_fake_git(bin_dir.path());
+    }
+    let mut c = common::ralf(temp);
+    c.env("PATH", common::prepend_to_path(bin_dir.path()))
+        .env("RALF_RC_FILE", temp.child("ralfrc").path())
+        .arg("connect")
+        .args(args);
+    c
+}
+
+#[test]
+fn connect_expands_forge_prefixes() {
+    for (input, flag, url) in [
+        (
+            "gitlab:me",
+            "--https",
+            "https://gitlab.com/me/ralf-conf.git",
+        ),
+        ("codeberg:me/dots", "--ssh", "git@codeberg.org:me/dots.git"),
+        (
+            "git.example.com:me/dots.git",
+            "--ssh",
+            "git@git.example.com:me/dots.git",
+        ),
+        ("me/dots", "--https", "https://github.com/me/dots.git"),
+    ] {
+        let temp = TempDir::new().unwrap();
+        connect(&temp, &[input, flag])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("Connecting to {}", url)));
+    }
+}
+
+#[test]
+fn connect_uses_the_default_forge_and_leaves_full_urls_alone() {
+    let temp = TempDir::new().unwrap();
+    connect(&temp, &["me", "--https"])
+        .env("RALF_FORGE", "git.example.com")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Connecting to https://git.example.com/me/ralf-conf.git",
+        ));
+
+    let temp = TempDir::new().unwrap();
+    connect(
+        &temp,
+        &["ssh://git@git.example.com/me/ralf-conf.git", "--yes"],
+    )
+    .env("RALF_FORGE", "gitlab")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+        "Connecting to ssh://git@git.example.com/me/ralf-conf.git",
+    ));
+
+    let temp = TempDir::new().unwrap();
+    connect(&temp, &["gitlab:me", "--yes"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "To connect to gitlab.com use --ssh or --https",
+        ));
+}
+
+#[test]
+fn connect_falls_back_to_alf_conf_on_any_forge() {
+    let temp = TempDir::new().unwrap();
+    // A git that only knows the legacy repo name
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let git = bin_dir.child("git");
+    git.write_str(
+        "#!/usr/bin/env sh\n\
+         if [ \"$1\" = clone ]; then\n\
+           case \"$2\" in *ralf-conf.git) echo \"not found: $2\" >&2; exit 128 ;; esac\n\
+           echo \"$2\" > \"$TMPDIR_LOG\"; mkdir -p \"$3\"; touch \"$3/ralf.conf\"\n\
+         fi\n\
+         exit 0\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(git.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    let log = temp.child("cloned");
+
+    connect(&temp, &["codeberg:me", "--https"])
+        .env("TMPDIR_LOG", log.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "retrying with https://codeberg.org/me/alf-conf.git",
+        ));
+    log.assert("https://codeberg.org/me/alf-conf.git\n");
+    temp.child("data/ralf/repo/ralf.conf")
+        .assert(predicate::path::exists());
+}
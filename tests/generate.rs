@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
 use assert_fs::TempDir;
 use predicates::prelude::*;
 mod common;
@@ -29,7 +30,10 @@ fn generate_when_config_present() {
 fn generate_when_config_missing() {
     let temp = TempDir::new().unwrap();
     let mut c = common::cmd();
-    c.current_dir(temp.path()).arg("generate");
+    c.current_dir(temp.path())
+        .env("HOME", temp.path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
+        .arg("generate");
     c.assert().failure().stdout(predicate::str::starts_with(
         "ERROR: Cannot find config file",
     ));
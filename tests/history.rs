@@ -0,0 +1,87 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+
+fn setup(temp: &TempDir) -> Command {
+    let repo = temp.child("ralf-conf");
+    if !repo.child("ralf.conf").path().exists() {
+        repo.child("ralf.conf").write_str("g: git\n").unwrap();
+    }
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+fn save_with(temp: &TempDir, conf: &str) {
+    temp.child("ralf-conf/ralf.conf").write_str(conf).unwrap();
+    setup(temp).arg("save").assert().success();
+}
+
+#[test]
+fn history_lists_generations_with_their_alias_changes() {
+    let temp = TempDir::new().unwrap();
+    save_with(&temp, "g: git\n");
+    save_with(&temp, "g: git\n  s: status\nk: kubectl\n");
+    // An unchanged save keeps no new generation
+    save_with(&temp, "g: git\n  s: status\nk: kubectl\n");
+
+    setup(&temp).arg("history").assert().success().stdout(
+        predicate::str::contains("  0  just now      machine box  (in use)\n")
+            .and(predicate::str::contains("       + g s  git status"))
+            .and(predicate::str::contains("       + k    kubectl"))
+            .and(predicate::str::contains("  1  just now      machine box\n"))
+            .and(predicate::str::contains("(oldest kept)"))
+            .and(predicate::str::contains("  2 ").not()),
+    );
+}
+
+#[test]
+fn rollback_restores_the_previous_generation_and_steps_further_back() {
+    let temp = TempDir::new().unwrap();
+    save_with(&temp, "a: one\n");
+    save_with(&temp, "b: two\n");
+    save_with(&temp, "c: three\n");
+    let aliases = temp.child("config/ralf/aliases.sh");
+    aliases.assert(predicate::str::contains("c()"));
+
+    setup(&temp)
+        .arg("rollback")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored generation 1"));
+    aliases.assert(predicate::str::contains("b()").and(predicate::str::contains("c()").not()));
+    temp.child("config/ralf/aliases.fish")
+        .assert(predicate::str::contains("function b"));
+
+    // Rolling back again goes one further, not back and forth
+    setup(&temp).arg("rollback").assert().success();
+    aliases.assert(predicate::str::contains("a()"));
+
+    setup(&temp).args(["rollback", "0"]).assert().success();
+    aliases.assert(predicate::str::contains("c()"));
+    setup(&temp)
+        .args(["rollback", "7"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no generation 7; 3 kept"));
+}
+
+#[test]
+fn save_keeps_a_bounded_number_of_generations() {
+    let temp = TempDir::new().unwrap();
+    for i in 0..12 {
+        save_with(&temp, &format!("a{}: echo {}\n", i, i));
+    }
+    let out = setup(&temp).args(["history", "--json"]).output().unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let list = v.as_array().unwrap();
+    assert_eq!(list.len(), 10);
+    assert_eq!(list[0]["machine"], "box");
+    assert_eq!(list[0]["in_use"], true);
+    assert_eq!(list[0]["changes"][0]["alias"], "a11");
+}
@@ -0,0 +1,100 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str(
+            "# Git shortcuts\ng: git\n  # pretty history\n  l: log --graph\n  s: status\ndc: docker compose\n",
+        )
+        .unwrap();
+    repo.child("machines/box.conf")
+        .write_str("g: hub\n")
+        .unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::cmd();
+    c.current_dir(temp.path())
+        .env("RALF_RC_FILE", rc.path())
+        .env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn list_shows_merged_aliases_with_layers() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("g    machine  hub"))
+        .stdout(predicate::str::contains("# Git shortcuts"))
+        .stdout(predicate::str::contains("g l  base     hub log --graph"))
+        .stdout(predicate::str::contains("dc   base     docker compose"));
+}
+
+#[test]
+fn list_filters_by_layer_and_prefix() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["list", "--layer", "machine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("g  machine  hub"))
+        .stdout(predicate::str::contains("dc").not());
+
+    setup(&temp)
+        .args(["list", "--prefix", "d"])
+        .assert()
+        .success()
+        .stdout("dc  base     docker compose\n");
+}
+
+#[test]
+fn list_json() {
+    let temp = TempDir::new().unwrap();
+    let out = setup(&temp)
+        .args(["list", "--json", "--prefix", "g"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let items = json.as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[1]["sub"], "l");
+    assert_eq!(items[1]["command"], "hub log --graph");
+    assert_eq!(items[1]["description"], "pretty history");
+    assert_eq!(items[1]["layer"], "base");
+    assert_eq!(items[1]["line"], 4);
+}
+
+#[test]
+fn search_matches_names_commands_and_descriptions() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["search", "history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("g l"));
+
+    setup(&temp)
+        .args(["search", "dcmp"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dc  base     docker compose"));
+
+    setup(&temp)
+        .args(["search", "nothing-like-this"])
+        .assert()
+        .failure()
+        .stdout("No aliases match 'nothing-like-this'\n");
+    setup(&temp)
+        .args(["search", "nothing-like-this", "--json"])
+        .assert()
+        .failure()
+        .stdout("[]\n");
+}
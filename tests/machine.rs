@@ -0,0 +1,216 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    if !repo.child("ralf.conf").path().exists() {
+        repo.child("ralf.conf")
+            .write_str("g: git\n  s: status\ndc: docker compose\n")
+            .unwrap();
+        repo.child("machines/web1.conf")
+            .write_str("@sudo: doas\ng: hub\n")
+            .unwrap();
+    }
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn machine_list_and_show() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["machine", "new", "box", "--from", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created"));
+    setup(&temp)
+        .args(["machine", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "* box     1 aliases  not committed",
+        ))
+        .stdout(predicate::str::contains(
+            "  web1    1 aliases  not committed",
+        ));
+    setup(&temp)
+        .args(["machine", "show", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Escalation: doas"))
+        .stdout(predicate::str::contains("g s  base     hub status"));
+}
+
+#[test]
+fn machine_new_rename_rm() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["machine", "new", "ci"])
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/ci.conf")
+        .assert(predicate::str::starts_with("# Overlay for machine 'ci'"));
+    setup(&temp)
+        .args(["machine", "new", "ci"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already has an overlay"));
+    setup(&temp)
+        .args(["machine", "new", "../x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid machine id"));
+
+    setup(&temp)
+        .args(["machine", "rename", "ci", "build"])
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/ci.conf")
+        .assert(predicate::path::missing());
+    setup(&temp)
+        .args(["machine", "rm", "build"])
+        .assert()
+        .success();
+    temp.child("ralf-conf/machines/build.conf")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn machine_name_still_sets_the_id() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["machine", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Machine set to 'web1'"));
+    temp.child("ralf-conf/.ralf_machine").assert("web1");
+}
+
+#[test]
+fn machine_rules_pick_the_id() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    temp.child("ralf-conf/machines/rules")
+        .write_str("# id: conditions\nwork: user=nobody-here\nlaptop: hostname=dhcp-* env:RALF_TEST_RULE=y*\noops\n")
+        .unwrap();
+    let run = |host: &str| {
+        let mut c = setup(&temp);
+        c.env_remove("RALF_MACHINE")
+            .env("HOSTNAME", host)
+            .env("USER", "alice")
+            .env("RALF_TEST_RULE", "yes")
+            .args(["machine", "--explain"]);
+        c
+    };
+    run("dhcp-42.example.net")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Machine: laptop\nSource:  rule at",
+        ))
+        .stdout(predicate::str::contains("no: user is alice"))
+        .stdout(predicate::str::contains("  4  error: expected"));
+    run("desk")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Machine: desk\nSource:  $HOSTNAME",
+        ));
+
+    // The environment variable still overrides the rules
+    setup(&temp)
+        .env("HOSTNAME", "dhcp-1")
+        .env("RALF_TEST_RULE", "yes")
+        .args(["machine", "--explain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Machine: box\nSource:  environment variable RALF_MACHINE",
+        ))
+        .stdout(predicate::str::contains(
+            "matches, but environment variable RALF_MACHINE decided",
+        ))
+        .stdout(predicate::str::contains("matched").not());
+}
+
+#[test]
+fn group_overlays_sit_between_base_and_machine() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("machines/rules")
+        .write_str("@servers: machine=web*\n@linux: os=linux\n@never: user=nobody-here\n")
+        .unwrap();
+    repo.child("machines/groups/servers.conf")
+        .write_str("g: git-server\n  s: status -s\ndc: podman compose\n")
+        .unwrap();
+    repo.child("machines/groups/never.conf")
+        .write_str("dc: never\n")
+        .unwrap();
+
+    // web1's own overlay wins over the group, the group over the base
+    setup(&temp)
+        .env("RALF_MACHINE", "web1")
+        .args(["list", "--layer", "group"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("g s")
+                .and(predicate::str::contains("podman compose"))
+                .and(predicate::str::contains("git-server").not())
+                .and(predicate::str::contains("never").not()),
+        );
+    setup(&temp)
+        .args(["diff", "--machine", "box", "--machine", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dc"));
+    setup(&temp)
+        .env("RALF_MACHINE", "web1")
+        .env("USER", "alice")
+        .args(["machine", "--explain"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("@servers")
+                .and(predicate::str::contains("in group"))
+                .and(predicate::str::contains("no: user is alice")),
+        );
+
+    repo.child("machines/rules")
+        .write_str("web: machine=web1\n")
+        .unwrap();
+    setup(&temp)
+        .args(["machine", "--explain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("only works in '@<group>:' rules"));
+}
+
+#[test]
+fn env_group_conditions_hold_only_for_this_machine() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("machines/rules")
+        .write_str("@ci: env:RALF_TEST_CI=1\n")
+        .unwrap();
+    repo.child("machines/groups/ci.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+
+    // This shell's variables say nothing about web1
+    setup(&temp)
+        .env("RALF_TEST_CI", "1")
+        .args(["diff", "--machine", "web1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- k"));
+}
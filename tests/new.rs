@@ -0,0 +1,109 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+use common::{git, has_git};
+
+fn ralf(temp: &TempDir) -> Command {
+    let mut c = common::ralf(temp);
+    c.env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn new_scaffolds_a_repo_connects_to_it_and_uploads() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    git(temp.path(), &["init", "-q", "--bare", "remote.git"]);
+    let url = format!("file://{}", temp.child("remote.git").path().display());
+
+    ralf(&temp)
+        .args(["new", "dots", "--remote", &url])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Created a config repo in ~/dots")
+                .and(predicate::str::contains("- ~/dots/machines/box.conf"))
+                .and(predicate::str::contains("ralf upload")),
+        );
+    let repo = temp.child("dots");
+    repo.child("ralf.conf")
+        .assert(predicate::str::starts_with("# ralf aliases"));
+    repo.child("machines/box.conf")
+        .assert(predicate::str::contains("machine 'box'"));
+    repo.child(".gitignore")
+        .assert(predicate::str::contains(".ralf_machine"));
+    assert_eq!(
+        git(repo.path(), &["remote", "get-url", "origin"]).trim(),
+        url
+    );
+    temp.child("config/ralf/config.toml")
+        .assert(predicate::str::contains("/dots\"\n"));
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::path::exists());
+
+    // Connected: the usual commands work from anywhere and push to the remote
+    ralf(&temp).args(["add", "g", "git"]).assert().success();
+    ralf(&temp)
+        .args(["upload", "-m", "first aliases"])
+        .assert()
+        .success();
+    let log = git(temp.child("remote.git").path(), &["log", "--format=%s"]);
+    assert_eq!(log.trim(), "first aliases");
+}
+
+#[test]
+fn new_defaults_to_the_data_dir_and_keeps_existing_configs() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    ralf(&temp)
+        .arg("new")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("remote add origin <url>"));
+    let repo = temp.child("data/ralf/repo");
+    repo.child("ralf.conf").assert(predicate::path::exists());
+    assert!(repo.child(".git").path().is_dir());
+
+    ralf(&temp)
+        .arg("new")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already has a ralf.conf"));
+}
+
+#[test]
+fn new_drops_the_old_branch_and_stores_a_clean_path() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    git(temp.path(), &["init", "-q", "--bare", "remote.git"]);
+    let url = format!("file://{}", temp.child("remote.git").path().display());
+    temp.child("fresh/.gitignore").write_str("*.bak\n").unwrap();
+    ralf(&temp)
+        .args(["config", "set", "branch", "work"])
+        .assert()
+        .success();
+
+    ralf(&temp)
+        .args(["new", "./fresh", "--remote", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- ~/fresh/.gitignore").not());
+    let settings = temp.child("config/ralf/config.toml");
+    settings.assert(predicate::str::contains("branch").not());
+    settings.assert(predicate::str::contains("/./").not());
+
+    ralf(&temp).args(["add", "g", "git"]).assert().success();
+    ralf(&temp)
+        .args(["upload", "-m", "first aliases"])
+        .assert()
+        .success();
+}
@@ -0,0 +1,62 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+
+fn setup(temp: &TempDir, conf: &str) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf").write_str(conf).unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn run_forwards_subcommand_and_arguments() {
+    let temp = TempDir::new().unwrap();
+    let conf = "e: echo\n  hi: hello\n  own: !printf '[%s]' \"$1\"\n";
+    setup(&temp, conf)
+        .args(["run", "e", "hi", "-n", "there"])
+        .assert()
+        .success()
+        .stdout("hello -n there\n");
+    setup(&temp, conf)
+        .args(["run", "e", "own", "a b"])
+        .assert()
+        .success()
+        .stdout("[a b]");
+    setup(&temp, conf)
+        .args(["run", "e", "--help"])
+        .assert()
+        .success()
+        .stdout("--help\n");
+}
+
+#[test]
+fn run_passes_exit_status_through() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp, "fail: !exit 7\n  x: !exit 3\n")
+        .args(["run", "fail", "x"])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn run_uses_sudo_policy_and_rejects_unknown_aliases() {
+    let temp = TempDir::new().unwrap();
+    let conf = "@sudo: none\nupd: @root echo updating\n";
+    setup(&temp, conf)
+        .args(["run", "upd"])
+        .assert()
+        .success()
+        .stdout("updating\n");
+    setup(&temp, conf)
+        .args(["run", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No such alias: nope"));
+}
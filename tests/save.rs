@@ -11,6 +11,8 @@ fn save_writes_aliases_and_tip() {
     let aliases = temp.child("aliases.txt");
     let mut c = common::cmd();
     c.current_dir(temp.path())
+        .env("HOME", temp.path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
         .env("ALF_ALIASES_FILE", aliases.path())
         .arg("save");
     c.assert()
@@ -29,6 +31,8 @@ fn save_missing_config_errors() {
     let aliases = temp.child("aliases.txt");
     let mut c = common::cmd();
     c.current_dir(temp.path())
+        .env("HOME", temp.path())
+        .env("XDG_DATA_HOME", temp.child("data").path())
         .env("ALF_ALIASES_FILE", aliases.path())
         .arg("save");
     c.assert().failure().stdout(predicate::str::starts_with(
@@ -0,0 +1,192 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+
+#[test]
+fn config_set_get_unset_and_list() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.child("config/ralf/config.toml");
+    common::ralf(&temp)
+        .args(["config", "set", "theme", "Lesbian"])
+        .assert()
+        .success()
+        .stdout("theme = lesbian\n");
+    common::ralf(&temp)
+        .args(["config", "set", "editor", "code --wait"])
+        .assert()
+        .success();
+    file.assert("theme = \"lesbian\"\neditor = \"code --wait\"\n");
+
+    // Setting again replaces the line in place and keeps comments
+    file.write_str("# mine\ntheme = \"trans\"\neditor = \"vi\"\n")
+        .unwrap();
+    common::ralf(&temp)
+        .args(["config", "set", "theme", "progress"])
+        .assert()
+        .success();
+    file.assert("# mine\ntheme = \"progress\"\neditor = \"vi\"\n");
+
+    common::ralf(&temp)
+        .args(["config", "get", "editor"])
+        .assert()
+        .success()
+        .stdout("vi\n");
+    common::ralf(&temp)
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("  theme         progress\n")
+                .and(predicate::str::contains("  branch        unset")),
+        );
+
+    common::ralf(&temp)
+        .args(["config", "unset", "editor"])
+        .assert()
+        .success();
+    file.assert("# mine\ntheme = \"progress\"\n");
+    common::ralf(&temp)
+        .args(["config", "get", "editor"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("editor is not set"));
+
+    common::ralf(&temp)
+        .args(["config", "set", "colour", "red"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown setting 'colour'"));
+    common::ralf(&temp)
+        .args(["config", "set", "sudo", "su"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown escalation tool 'su'"));
+}
+
+#[test]
+fn env_vars_override_and_old_files_are_still_read() {
+    let temp = TempDir::new().unwrap();
+    temp.child(".ralf_theme").write_str("bisexual").unwrap();
+    temp.child("dots/ralf.conf").write_str("g: git\n").unwrap();
+    temp.child(".ralfrc")
+        .write_str(&format!("{}\n", temp.child("dots").path().display()))
+        .unwrap();
+
+    common::ralf(&temp)
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("theme         bisexual  (from ~/.ralf_theme)")
+                .and(predicate::str::contains("/dots  (from ~/.ralfrc)")),
+        );
+
+    common::ralf(&temp)
+        .args(["config", "set", "machine", "box"])
+        .assert()
+        .success();
+    common::ralf(&temp)
+        .args(["config", "set", "theme", "intersex"])
+        .env("RALF_THEME", "trans")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: environment variable RALF_THEME takes precedence here",
+        ));
+    common::ralf(&temp)
+        .args(["config", "get", "theme"])
+        .assert()
+        .success()
+        .stdout("intersex\n");
+
+    common::ralf(&temp)
+        .args(["machine", "--explain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("box").and(predicate::str::contains("config.toml")));
+    common::ralf(&temp)
+        .args(["machine", "--explain"])
+        .env("ALF_MACHINE", "web1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("environment variable ALF_MACHINE"));
+}
+
+#[test]
+fn settings_hold_the_repo_aliases_file_and_sudo_default() {
+    let temp = TempDir::new().unwrap();
+    temp.child("dots/ralf.conf")
+        .write_str("upd: sudo apt update\n")
+        .unwrap();
+    common::ralf(&temp)
+        .args(["config", "set", "repo", "dots"])
+        .assert()
+        .success();
+    common::ralf(&temp)
+        .args(["config", "set", "aliases_file", "~/out/aliases"])
+        .assert()
+        .success();
+    common::ralf(&temp)
+        .args(["config", "set", "sudo", "doas"])
+        .assert()
+        .success();
+
+    common::ralf(&temp).arg("save").assert().success();
+    temp.child("out/aliases")
+        .assert(predicate::str::contains("doas").and(predicate::str::contains("upd()")));
+}
+
+#[test]
+fn exported_env_from_the_aliases_file_does_not_hide_the_settings() {
+    let temp = TempDir::new().unwrap();
+    temp.child("dots/ralf.conf").write_str("g: git\n").unwrap();
+    temp.child("elsewhere").create_dir_all().unwrap();
+    common::ralf(&temp)
+        .args(["config", "set", "repo", "dots"])
+        .assert()
+        .success();
+    common::ralf(&temp)
+        .args(["config", "set", "machine", "box"])
+        .assert()
+        .success();
+
+    // What older aliases files exported, with no ~/.ralfrc behind it
+    common::ralf(&temp)
+        .current_dir(temp.child("elsewhere").path())
+        .arg("save")
+        .env("ralf_RC_FILE", temp.child(".ralfrc").path())
+        .env("ALF_RC_FILE", temp.child(".ralfrc").path())
+        .env("RALF_MACHINE", "oldhost")
+        .env("ralf_MACHINE", "oldhost")
+        .assert()
+        .success();
+    let sh = temp.child("config/ralf/aliases.sh");
+    sh.assert(
+        predicate::str::contains("_RC_FILE")
+            .not()
+            .and(predicate::str::contains("_MACHINE").not()),
+    );
+
+    // A shell that loaded the new file still finds the repo and the machine
+    let mut shell = Command::new("bash");
+    for (k, v) in common::ralf(&temp).get_envs() {
+        match v {
+            Some(v) => shell.env(k, v),
+            None => shell.env_remove(k),
+        };
+    }
+    shell
+        .current_dir(temp.child("elsewhere").path())
+        .env("RALF_BIN", common::cmd().get_program())
+        .env("ALIASES", sh.path())
+        .args([
+            "-c",
+            ". \"$ALIASES\" && \"$RALF_BIN\" save >/dev/null && \"$RALF_BIN\" machine --explain",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("box").and(predicate::str::contains("config.toml")));
+}
@@ -0,0 +1,104 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::fs;
+mod common;
+
+fn setup(temp: &TempDir) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    repo.create_dir_all().unwrap();
+    fs::write(
+        repo.child("ralf.conf"),
+        b"g: git\n  l: log\n  s: status\nupd: apt update\n",
+    )
+    .unwrap();
+    let rc = temp.child("ralfrc");
+    fs::write(&rc, repo.path().to_string_lossy().as_bytes()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path())
+        .env("RALF_ALIASES_FILE", temp.child("aliases.txt").path())
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION");
+    c
+}
+
+#[test]
+fn stats_off_by_default() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage statistics are off."));
+
+    setup(&temp).arg("save").assert().success();
+    let sh = fs::read_to_string(temp.child("config/ralf/aliases.sh").path()).unwrap();
+    assert!(!sh.contains("__ralf_log"));
+}
+
+#[test]
+fn stats_enable_instruments_generated_functions() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp)
+        .args(["stats", "--enable"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage statistics enabled"));
+
+    let sh = fs::read_to_string(temp.child("config/ralf/aliases.sh").path()).unwrap();
+    assert!(sh.contains("__ralf_log() {"));
+    assert!(sh.contains("      __ralf_log g l\n"));
+    assert!(sh.contains("  __ralf_log upd\n"));
+    let fish = fs::read_to_string(temp.child("config/ralf/aliases.fish").path()).unwrap();
+    assert!(fish.contains("function __ralf_log"));
+    assert!(fish.contains("      __ralf_log g s\n"));
+
+    setup(&temp).args(["stats", "--disable"]).assert().success();
+    let sh = fs::read_to_string(temp.child("config/ralf/aliases.sh").path()).unwrap();
+    assert!(!sh.contains("__ralf_log"));
+}
+
+#[test]
+fn stats_reports_most_used_never_used_and_stale() {
+    let temp = TempDir::new().unwrap();
+    let log = temp.child("config/ralf/usage.log");
+    log.write_str(
+        "4000000000\tbox\tg\tl\n4000000000\tbox\tg\tl\n1000\tbox\tupd\t\n4000000000\tother\tg\ts\n",
+    )
+    .unwrap();
+
+    setup(&temp)
+        .args(["stats", "--suggest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Machine: box (3 calls recorded)"))
+        .stdout(predicate::str::contains("     2  g l"))
+        .stdout(predicate::str::contains("Never used:\n  g\n  g s\n"))
+        .stdout(predicate::str::contains("  upd  (last used"))
+        .stdout(predicate::str::contains("Removal candidates:\n  upd\n"));
+}
+
+#[test]
+fn stats_for_another_machine_uses_its_aliases() {
+    let temp = TempDir::new().unwrap();
+    temp.child("ralf-conf/machines/other.conf")
+        .write_str("k: kubectl\n")
+        .unwrap();
+    temp.child("config/ralf/usage.log")
+        .write_str("4000000000\tother\tg\ts\n")
+        .unwrap();
+
+    setup(&temp)
+        .args(["stats", "--machine", "other"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Machine: other (1 calls recorded)",
+        ))
+        .stdout(predicate::str::contains(
+            "Never used:\n  g\n  g l\n  upd\n  k\n",
+        ));
+}
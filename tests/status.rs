@@ -0,0 +1,101 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+use common::{git, has_git};
+
+/// A clone of a local bare repo with a base config and an overlay for `box`.
+fn setup(temp: &TempDir) -> Command {
+    let repo = temp.child("ralf-conf");
+    if !repo.path().exists() {
+        git(temp.path(), &["init", "-q", "--bare", "remote.git"]);
+        git(temp.path(), &["clone", "-q", "remote.git", "ralf-conf"]);
+        repo.child("ralf.conf").write_str("g: git\n").unwrap();
+        repo.child("machines/box.conf")
+            .write_str("g: hub\n")
+            .unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-qm", "init"]);
+        git(repo.path(), &["push", "-q", "origin", "HEAD"]);
+    }
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn status_groups_changes_by_layer_and_counts_unpushed_commits() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("machines/web1.conf")
+        .write_str("g: lab\n")
+        .unwrap();
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "-qm", "web1"]);
+    repo.child("ralf.conf")
+        .write_str("g: git\n  s: status\n")
+        .unwrap();
+    repo.child("machines/box.conf")
+        .write_str("g: hub\n  l: log\n")
+        .unwrap();
+
+    setup(&temp).arg("status").assert().success().stdout(
+        predicate::str::contains("1 ahead and 0 behind origin/")
+            .and(predicate::str::contains("  base\n     M ralf.conf"))
+            .and(predicate::str::contains(
+                "  machine box (this machine)\n     M machines/box.conf",
+            ))
+            .and(predicate::str::contains("aliases.sh: missing"))
+            .and(predicate::str::contains("ralf aliases not loaded")),
+    );
+}
+
+#[test]
+fn status_json_reports_stale_files_and_the_loaded_generation() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(&temp).arg("save").assert().success();
+    let sh = std::fs::read_to_string(temp.child("config/ralf/aliases.sh").path()).unwrap();
+    let generation = sh
+        .split("RALF_GENERATION=\"")
+        .nth(1)
+        .and_then(|s| s.split('"').next())
+        .unwrap()
+        .to_string();
+
+    let out = setup(&temp)
+        .args(["status", "--json"])
+        .env("RALF_GENERATION", &generation)
+        .output()
+        .unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["shell"]["state"], "current");
+    assert_eq!(v["generated"][0]["state"], "current");
+
+    // A config edit after the save makes the files, and so the shell, stale
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    temp.child("ralf-conf/ralf.conf")
+        .write_str("g: git\n  s: status\n")
+        .unwrap();
+    let out = setup(&temp)
+        .args(["status", "--json"])
+        .env("RALF_GENERATION", "1")
+        .output()
+        .unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(v["shell"]["state"], "stale");
+    assert_eq!(v["generated"][0]["state"], "stale");
+    assert_eq!(v["changes"][0]["layer"], "base");
+    assert_eq!(v["upstream"]["ahead"], 0);
+}
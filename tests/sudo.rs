@@ -0,0 +1,231 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::fs;
+mod common;
+
+fn setup_repo(temp: &TempDir, machine_overlay: &str) -> String {
+    let repo = temp.child("ralf-conf");
+    repo.create_dir_all().unwrap();
+    fs::write(
+        repo.child("ralf.conf"),
+        b"upd: sudo apt update\napt: sudo apt\n  up: upgrade\n",
+    )
+    .unwrap();
+    repo.child("machines").create_dir_all().unwrap();
+    fs::write(repo.child("machines/box.conf"), machine_overlay).unwrap();
+    let rc = temp.child("ralfrc");
+    fs::write(&rc, repo.path().to_string_lossy().as_bytes()).unwrap();
+    rc.path().to_string_lossy().into_owned()
+}
+
+#[test]
+fn sh_output_routes_sudo_through_wrapper() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_repo(&temp, "");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("__ralf_sudo() {"))
+        .stdout(predicate::str::contains("if sudo -n true"))
+        .stdout(predicate::str::contains("RALF_SUDO_NO_PROMPT"))
+        .stdout(predicate::str::contains("  __ralf_sudo apt update \"$@\""))
+        .stdout(predicate::str::contains(
+            "      __ralf_sudo apt upgrade \"$@\"",
+        ));
+}
+
+#[test]
+fn machine_overlay_selects_escalation_tool() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_repo(&temp, "@sudo: doas\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("if doas -n true"))
+        .stdout(predicate::str::contains("sudo -n").not());
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/usr/bin/fish")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  if doas -n true"))
+        .stdout(predicate::str::contains("__ralf_sudo apt update $argv"));
+}
+
+#[test]
+fn escalation_none_runs_commands_directly() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_repo(&temp, "@sudo: none\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("__ralf_sudo() {\n  \"$@\"\n}"));
+}
+
+#[test]
+fn unknown_escalation_tool_errors() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_repo(&temp, "@sudo: please\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", rc)
+        .env("RALF_MACHINE", "box")
+        .arg("generate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown escalation tool 'please'"));
+}
+
+fn setup_root_repo(temp: &TempDir, machine_overlay: &str) -> String {
+    let rc = setup_repo(temp, machine_overlay);
+    let repo = temp.child("ralf-conf");
+    fs::write(
+        repo.child("ralf.conf"),
+        b"upd: @root apt update\napt: apt\n  up: @root upgrade\n  ls: list\n",
+    )
+    .unwrap();
+    rc
+}
+
+#[test]
+fn root_annotation_adds_sudo_by_default() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_root_repo(&temp, "");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .args(["which", "upd"])
+        .assert()
+        .success()
+        .stdout("sudo apt update\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .args(["which", "apt", "up"])
+        .assert()
+        .success()
+        .stdout("sudo apt upgrade\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "if [ \"$(id -u)\" = 0 ] && [ \"${1#-}\" = \"$1\" ]; then",
+        ))
+        .stdout(predicate::str::contains("      command apt list \"$@\""))
+        .stdout(predicate::str::contains("@root").not());
+}
+
+#[test]
+fn root_policy_never_drops_sudo() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_root_repo(&temp, "@root: never\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .args(["which", "apt", "up"])
+        .assert()
+        .success()
+        .stdout("apt upgrade\n");
+}
+
+#[test]
+fn root_policy_always_skips_root_check() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_root_repo(&temp, "@root: always\n");
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  __ralf_sudo apt update \"$@\""))
+        .stdout(predicate::str::contains("id -u").not());
+}
+
+/// The root check lives in the shared wrapper, so as root it also skips the
+/// tool for literal `sudo` aliases, except those passing the tool options.
+#[test]
+fn root_policy_auto_applies_to_literal_sudo_aliases() {
+    let temp = TempDir::new().unwrap();
+    let rc = setup_repo(&temp, "");
+    fs::write(
+        temp.child("ralf-conf/ralf.conf"),
+        b"hi: sudo echo hi\nbob: sudo -u bob echo hi\nup: @root echo up\n",
+    )
+    .unwrap();
+    let bin = temp.child("bin");
+    bin.create_dir_all().unwrap();
+    for (name, body) in [("id", "echo 0"), ("sudo", "echo \"sudo $*\"")] {
+        let script = bin.child(name);
+        fs::write(&script, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+    let out = common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", &rc)
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .arg("generate")
+        .output()
+        .unwrap();
+    temp.child("aliases.sh").write_binary(&out.stdout).unwrap();
+
+    std::process::Command::new("bash")
+        .arg("-c")
+        .arg(". ./aliases.sh; hi; bob; up")
+        .current_dir(temp.path())
+        .env("PATH", common::prepend_to_path(bin.path()))
+        .assert()
+        .success()
+        .stdout("hi\nsudo -u bob echo hi\nup\n");
+}
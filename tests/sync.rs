@@ -0,0 +1,141 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+
+/// A shared directory (as Syncthing or an NFS mount would provide) with a
+/// base config and one machine overlay; no git involved.
+fn setup(temp: &TempDir) {
+    let share = temp.child("share");
+    share.child("ralf.conf").write_str("g: git\n").unwrap();
+    share
+        .child("machines/box.conf")
+        .write_str("g: hub\n")
+        .unwrap();
+}
+
+fn ralf(temp: &TempDir) -> Command {
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", temp.child("ralfrc").path())
+        .env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn dir_backend_uses_the_synced_directory_in_place() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let share = temp.child("share");
+    ralf(&temp)
+        .args(["connect", &share.path().to_string_lossy(), "--sync", "dir"])
+        .assert()
+        .success();
+    temp.child("ralfrc")
+        .assert(predicate::str::ends_with("/share\n"));
+    temp.child("config/ralf/config.toml")
+        .assert("sync = \"dir\"\n");
+    assert!(!temp.child("data/ralf/repo").path().exists());
+
+    // Another machine edits the shared file; download only regenerates
+    share.child("ralf.conf").write_str("k: kubectl\n").unwrap();
+    ralf(&temp)
+        .arg("download")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("synced outside ralf"));
+    temp.child("config/ralf/aliases.sh")
+        .assert(predicate::str::contains("k()"));
+    ralf(&temp)
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to upload"));
+}
+
+#[test]
+fn copy_backend_mirrors_shared_files_both_ways() {
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let share = temp.child("share");
+    let repo = temp.child("data/ralf/copy");
+    ralf(&temp)
+        .args(["connect", &share.path().to_string_lossy(), "--sync", "copy"])
+        .assert()
+        .success();
+    // Connecting again reuses the copy; a directory holding anything else is left alone
+    ralf(&temp)
+        .args(["connect", &share.path().to_string_lossy(), "--sync", "copy"])
+        .assert()
+        .success();
+    temp.child("clone/ralf.conf").write_str("x: ls\n").unwrap();
+    ralf(&temp)
+        .args(["connect", &share.path().to_string_lossy(), "--sync", "copy"])
+        .args(["--dir", "clone"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("~/clone is not empty"));
+    repo.child("machines/box.conf").assert("g: hub\n");
+
+    share
+        .child("ralf.conf")
+        .write_str("g: git\nk: kubectl\n")
+        .unwrap();
+    share
+        .child("machines/web1.conf")
+        .write_str("g: lab\n")
+        .unwrap();
+    std::fs::remove_file(share.child("machines/box.conf").path()).unwrap();
+    ralf(&temp).arg("download").assert().success().stdout(
+        predicate::str::contains("updated ralf.conf")
+            .and(predicate::str::contains("added machines/web1.conf"))
+            .and(predicate::str::contains("removed machines/box.conf")),
+    );
+    assert!(!repo.child("machines/box.conf").path().exists());
+
+    // Overlays made here and not uploaded yet are kept, not removed
+    repo.child("machines/groups/lab.conf")
+        .write_str("g: lab\n")
+        .unwrap();
+    ralf(&temp).arg("download").assert().success().stdout(
+        predicate::str::contains("kept machines/groups/lab.conf")
+            .and(predicate::str::contains("upload them")),
+    );
+    repo.child("machines/groups/lab.conf").assert("g: lab\n");
+    std::fs::remove_file(repo.child("machines/groups/lab.conf").path()).unwrap();
+
+    // Local overlays stay on this machine
+    repo.child("ralf.local.conf").write_str("l: ls\n").unwrap();
+    repo.child("ralf.conf").write_str("g: git\n").unwrap();
+    ralf(&temp)
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("updated ralf.conf"));
+    share.child("ralf.conf").assert("g: git\n");
+    assert!(!share.child("ralf.local.conf").path().exists());
+
+    // Upload adds but never deletes in the shared directory
+    share
+        .child("machines/web2.conf")
+        .write_str("g: new\n")
+        .unwrap();
+    ralf(&temp)
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept machines/web2.conf"));
+    share.child("machines/web2.conf").assert("g: new\n");
+
+    ralf(&temp)
+        .arg("download")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("added machines/web2.conf"));
+    ralf(&temp)
+        .arg("download")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Already up to date"));
+}
@@ -0,0 +1,121 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use std::process::Command;
+mod common;
+
+const NEW_BINARY: &str = "#!/bin/sh\necho new ralf\n";
+const NEW_SHA256: &str = "de6d312ba751ad04a7e1a9f86af6a214537cc6159553979b503a484e3ccf9fb3";
+
+/// A copy of ralf to upgrade, so the binary under test stays untouched.
+fn installed(temp: &TempDir) -> Command {
+    let bin = temp.child("bin/ralf");
+    if !bin.path().exists() {
+        temp.child("bin").create_dir_all().unwrap();
+        std::fs::copy(common::cmd().get_program(), bin.path()).unwrap();
+    }
+    let mut c = Command::new(bin.path());
+    c.current_dir(temp.path())
+        .env("HOME", temp.path())
+        .env("XDG_CONFIG_HOME", temp.child("config").path())
+        .env_remove("RALF_UPGRADE_URL");
+    c
+}
+
+/// A release feed in a local directory: manifest.json next to the asset.
+fn release(temp: &TempDir, version: &str, sha256: &str) -> String {
+    let feed = temp.child("feed");
+    feed.child("ralf-new").write_str(NEW_BINARY).unwrap();
+    let target = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+    feed.child("manifest.json")
+        .write_str(&format!(
+            r#"{{"version": "{}", "assets": {{"{}": {{"url": "ralf-new", "sha256": "{}"}}}}}}"#,
+            version, target, sha256
+        ))
+        .unwrap();
+    format!("file://{}", feed.child("manifest.json").path().display())
+}
+
+#[test]
+fn check_reports_without_changing_anything() {
+    let temp = TempDir::new().unwrap();
+    let manifest = release(&temp, "99.0.0", NEW_SHA256);
+    installed(&temp)
+        .args(["upgrade", "--check", "--manifest", &manifest])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "ralf 99.0.0 is available (you have {})",
+            env!("CARGO_PKG_VERSION")
+        )));
+    assert!(!temp.child("bin/ralf.bak").path().exists());
+
+    let manifest = release(&temp, env!("CARGO_PKG_VERSION"), NEW_SHA256);
+    installed(&temp)
+        .args(["upgrade", "--manifest", &manifest])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is up to date"));
+
+    // Pre-releases are not offered
+    let manifest = release(&temp, "99.0.0-rc1", NEW_SHA256);
+    installed(&temp)
+        .args(["upgrade", "--manifest", &manifest])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ralf 99.0.0-rc1 is a pre-release"));
+    assert!(!temp.child("bin/ralf.bak").path().exists());
+
+    installed(&temp)
+        .arg("upgrade")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no release manifest configured"));
+}
+
+#[test]
+fn upgrade_verifies_and_replaces_the_binary_keeping_a_backup() {
+    let temp = TempDir::new().unwrap();
+    let manifest = release(&temp, "99.0.0", NEW_SHA256);
+    installed(&temp)
+        .args(["config", "set", "upgrade_url", &manifest])
+        .assert()
+        .success();
+    installed(&temp)
+        .arg("upgrade")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Upgraded ralf {} -> 99.0.0",
+            env!("CARGO_PKG_VERSION")
+        )));
+
+    Command::new(temp.child("bin/ralf").path())
+        .assert()
+        .success()
+        .stdout("new ralf\n");
+    Command::new(temp.child("bin/ralf.bak").path())
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn checksum_mismatch_leaves_the_binary_alone() {
+    let temp = TempDir::new().unwrap();
+    let manifest = release(&temp, "99.0.0", &"0".repeat(64));
+    installed(&temp)
+        .args(["upgrade", "--manifest", &manifest])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("checksum mismatch"));
+    installed(&temp).arg("--version").assert().success();
+    let leftovers: Vec<_> = std::fs::read_dir(temp.child("bin").path())
+        .unwrap()
+        .flatten()
+        .map(|e| e.file_name())
+        .collect();
+    assert_eq!(leftovers, vec![std::ffi::OsString::from("ralf")]);
+}
@@ -0,0 +1,131 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+mod common;
+use common::{git, has_git};
+
+/// A clone of a local bare repo holding a base config and one machine overlay.
+fn setup(temp: &TempDir) -> std::process::Command {
+    setup_with(
+        temp,
+        &[
+            ("ralf.conf", "g: git\n  s: status\ndc: docker compose\n"),
+            ("machines/web1.conf", "g: hub\n"),
+        ],
+    )
+}
+
+/// A clone of a local bare repo whose first commit holds `files`.
+fn setup_with(temp: &TempDir, files: &[(&str, &str)]) -> std::process::Command {
+    let repo = temp.child("ralf-conf");
+    if !repo.path().exists() {
+        git(temp.path(), &["init", "-q", "--bare", "remote.git"]);
+        git(temp.path(), &["clone", "-q", "remote.git", "ralf-conf"]);
+        for (file, content) in files {
+            repo.child(file).write_str(content).unwrap();
+        }
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-qm", "init"]);
+        git(repo.path(), &["push", "-q", "origin", "HEAD"]);
+    }
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    let mut c = common::ralf(temp);
+    c.env("RALF_RC_FILE", rc.path()).env("RALF_MACHINE", "box");
+    c
+}
+
+#[test]
+fn upload_describes_alias_changes_and_skips_unmanaged_files() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str("g: git\n  s: status -sb\n  l: log\n")
+        .unwrap();
+    repo.child("machines/web1.conf")
+        .write_str("g: hub\n  up: pull\n")
+        .unwrap();
+    repo.child("notes.txt").write_str("junk\n").unwrap();
+
+    setup(&temp)
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Not staged").and(predicate::str::contains("notes.txt")));
+    let log = git(repo.path(), &["log", "-1", "--format=%s"]);
+    assert!(
+        log.starts_with("change g.s, add g.l, remove dc; add g.up on machine web1"),
+        "{}",
+        log
+    );
+    assert!(git(repo.path(), &["status", "--short"]).contains("?? notes.txt"));
+}
+
+#[test]
+fn upload_with_message_and_all() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup(&temp);
+    let repo = temp.child("ralf-conf");
+    repo.child("notes.txt").write_str("junk\n").unwrap();
+
+    setup(&temp)
+        .args(["upload", "--all", "-m", "keep notes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Message: keep notes"));
+    assert_eq!(
+        git(repo.path(), &["log", "-1", "--format=%s"]),
+        "keep notes\n"
+    );
+    assert_eq!(git(repo.path(), &["status", "--short"]), "");
+}
+
+#[test]
+fn upload_without_machines_commits_only_managed_files() {
+    if !has_git() {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    setup_with(&temp, &[("ralf.conf", "g: git\n")]);
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str("g: git\nk: kubectl\n")
+        .unwrap();
+    // Staged by the user, not for ralf to push
+    repo.child("notes.txt").write_str("junk\n").unwrap();
+    git(repo.path(), &["add", "notes.txt"]);
+
+    setup_with(&temp, &[])
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Message: add k; update .gitignore",
+        ));
+    let remote = temp.child("remote.git");
+    assert_eq!(
+        git(remote.path(), &["log", "-1", "--format=%s"]),
+        "add k; update .gitignore\n"
+    );
+    let pushed = git(remote.path(), &["ls-tree", "-r", "--name-only", "HEAD"]);
+    assert!(!pushed.contains("notes.txt"), "{}", pushed);
+    assert!(pushed.contains(".gitignore"), "{}", pushed);
+    assert_eq!(git(repo.path(), &["status", "--short"]), "A  notes.txt\n");
+
+    // Nothing left to commit: no empty commit, just the push
+    setup_with(&temp, &[])
+        .arg("upload")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes to commit"));
+    assert_eq!(git(remote.path(), &["rev-list", "--count", "HEAD"]), "2\n");
+}
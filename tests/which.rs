@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
 use assert_fs::TempDir;
 use predicates::prelude::*;
 mod common;
@@ -55,3 +56,36 @@ fn which_unknown_errors() {
         .failure()
         .stdout(predicate::str::starts_with("Error: No such alias: no"));
 }
+
+#[test]
+fn which_trace_shows_layers() {
+    let temp = TempDir::new().unwrap();
+    let repo = temp.child("ralf-conf");
+    repo.child("ralf.conf")
+        .write_str("g: git\n  l: log --all\n")
+        .unwrap();
+    repo.child("machines/box.conf")
+        .write_str("g: hub\n  l: log --oneline\n")
+        .unwrap();
+    let rc = temp.child("ralfrc");
+    rc.write_str(&repo.path().to_string_lossy()).unwrap();
+
+    common::cmd()
+        .current_dir(temp.path())
+        .env("RALF_RC_FILE", rc.path())
+        .env("RALF_MACHINE", "box")
+        .env("SHELL", "/bin/bash")
+        .env_remove("FISH_VERSION")
+        .args(["which", "g", "l", "--trace"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "g\n  defined    ralf.conf:1          git\n  overridden machines/box.conf:1  hub\n",
+        ))
+        .stdout(predicate::str::contains(
+            "g l\n  defined    ralf.conf:2          log --all\n  overridden machines/box.conf:2  log --oneline\n",
+        ))
+        .stdout(predicate::str::contains(
+            "bash/zsh: hub log --oneline \"$@\"",
+        ));
+}